@@ -5,10 +5,6 @@
 
 //! Advent of Code Day 1.
 
-use std::collections::HashSet;
-use std::io;
-use std::io::prelude::*;
-
 extern crate aoc;
 
 /// Read the instance description from stdin and walk the
@@ -18,48 +14,32 @@ extern crate aoc;
 /// failing if the path does not self-intersect.
 ///
 /// This function probably needs to be split up.
-fn traverse(stop_short: bool) -> (isize, isize) {
-    // North, West, East, South.
-    let directions = [(0, 1), (-1, 0), (0, -1), (1, 0)];
-
+fn traverse(stop_short: bool) -> aoc::SPoint {
     // Read the single string from the input file.
-    let mut stdin = io::stdin();
-    let mut s = String::new();
-    let _ = stdin.read_to_string(&mut s).unwrap();
+    let s = aoc::input_string();
 
     // Set up state variables.
-    let mut position = (0, 0);
-    let mut trail = HashSet::new();
-    trail.insert(position);
-    let mut facing = 0;
+    let mut turtle = aoc::Turtle::new(aoc::SPoint::ORIGIN, aoc::Heading::North);
+    let mut trail = aoc::Trail::new(turtle.posn);
 
     // Walk the turns.
     for t in s.trim().split(", ") {
         // Calculate the new facing and distance.
         let turn = t.chars().next().unwrap();
-        facing = match turn {
-            'L' => (facing + 1) % 4,
-            'R' => (facing + 3) % 4,
-            _ => panic!("bad direction"),
-        };
-        let step: usize = (&t[1..]).parse().unwrap();
+        turtle.turn(aoc::Turn::from_letter(turn).expect("bad direction"));
+        let step: usize = t[1..].parse().unwrap();
 
-        // Walk the line from current position to new.
-        for _ in 0..step {
-            position.0 += directions[facing].0;
-            position.1 += directions[facing].1;
-            if stop_short && trail.contains(&position) {
-                // Trail has self-intersected.
-                return position;
-            }
-            trail.insert(position);
+        // Walk the line from current position to new,
+        // stopping if the trail has self-intersected.
+        if !turtle.walk(step, |p| trail.visit(p) || !stop_short) {
+            return turtle.posn;
         }
     }
     // Finish up.
     if stop_short {
         panic!("did not find self-intersection")
     };
-    position
+    turtle.posn
 }
 
 /// Do the traversal, then print the manhattan distance from
@@ -67,5 +47,5 @@ fn traverse(stop_short: bool) -> (isize, isize) {
 pub fn main() {
     let part = aoc::get_part();
     let position = traverse(part == 2);
-    println!("{}", position.0.abs() + position.1.abs());
+    println!("{}", position.manhattan_distance(aoc::SPoint::ORIGIN));
 }
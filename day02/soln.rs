@@ -7,78 +7,50 @@
 
 extern crate aoc;
 
+/// Return the key at the given position, if any.
+fn key_at(board: &aoc::Grid<char>, posn: aoc::SPoint) -> Option<char> {
+    let posn = aoc::Point::try_from(posn).ok()?;
+    board.get(posn).cloned().filter(|&c| c != '.')
+}
+
 /// Take a board, a starting point and a line of traversal
 /// instructions and return the ending position.
-fn walk_line(board: &[Vec<char>], n_start: (usize, usize), insns: &str) -> (usize, usize) {
-    let mut n = n_start;
+fn walk_line(board: &aoc::Grid<char>, n_start: aoc::SPoint, insns: &str) -> aoc::SPoint {
+    let mut turtle = aoc::Turtle::new(n_start, aoc::Heading::North);
 
-    // Walk over the instructions trying moves.
+    // Walk over the instructions trying moves, skipping
+    // steps that would walk off the keypad.
     for c in insns.chars() {
-        // Find next position from direction.
-        let next_n = match c {
-            'U' => (n.0, n.1 - 1),
-            'D' => (n.0, n.1 + 1),
-            'L' => (n.0 - 1, n.1),
-            'R' => (n.0 + 1, n.1),
-            _ => panic!("bad direction"),
-        };
-
-        // Skip steps that would walk off the keypad.
-        if board[next_n.1][next_n.0] != '.' {
-            n = next_n;
-        }
+        turtle.face(aoc::Heading::from_letter(c).expect("bad direction"));
+        turtle.step_if(|p| key_at(board, p).is_some());
     }
-    n
+    turtle.posn
 }
 
 /// Print the bathroom code for the instance.
-pub fn print_code(board: &[Vec<char>]) {
+pub fn print_code(board: &aoc::Grid<char>) {
     // Set up state.
-    let c = board.len() / 2 + 1;
-    let mut n = (c, c);
+    let n_start = board.find(|&c| c == '5').expect("no 5 key");
+    let mut n = aoc::SPoint::try_from(n_start).expect("bad 5 key");
 
     // Read strings from the input file and process them.
     for line in aoc::input_lines() {
         n = walk_line(board, n, &line);
-        print!("{}", board[n.1][n.0]);
+        print!("{}", key_at(board, n).expect("off keypad"));
     }
     println!();
 }
 
-/// Read the board for the part, returning a nested
-/// vector of the board surrounded by stops (`.`).
-pub fn read_board(part: usize) -> Vec<Vec<char>> {
-    // Set up state.
-    let mut result = Vec::new();
-    // Read each line of the board file.
+/// Read the board for the part. Spaces off the keypad are
+/// marked with `.`.
+pub fn read_board(part: usize) -> aoc::Grid<char> {
     let board_lines =
         aoc::input_file_lines(&format!("board{}.txt", part)).expect("could not open board file");
-    for line in board_lines {
-        // Start a new row, add left boundary.
-        let mut row = Vec::new();
-        row.push('.');
-
-        // Wrap up the characters of the row.
-        let chars = line.chars().collect::<Vec<char>>();
-        row.extend_from_slice(&chars);
-
-        // Push the right boundary and save the row.
-        row.push('.');
-        result.push(row);
-    }
-
-    // Add the top and bottom boundaries.
-    if result.is_empty() {
+    let board = aoc::Grid::from_lines(board_lines, |c| c);
+    if board.height() == 0 {
         panic!("empty board");
     };
-    let nrow = result[0].len();
-    let mut stops = Vec::with_capacity(nrow);
-    stops.resize(nrow, '.');
-    result.insert(0, stops.clone());
-    result.push(stops.clone());
-
-    // Return the result.
-    result
+    board
 }
 
 /// Read the appropriate board, then print its code.
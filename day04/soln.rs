@@ -6,7 +6,6 @@
 //! Advent of Code Day 4.
 
 extern crate aoc;
-extern crate regex;
 
 /// Return the "checksum" of the given room name.
 fn name_checksum(room_name: &str) -> String {
@@ -74,34 +73,25 @@ pub fn main() {
     assert!(part == 1 || part == 2);
     let decrypt = part == 2;
 
-    // Set up the regex for room encryption.
-    let room_pattern =
-        regex::Regex::new(r"^(.*)-(\d+)\[(.*)\]$").expect("main: could not compile regex");
+    // Set up the pattern for room encryption.
+    let room_pattern = aoc::LinePattern::new("{name}-{sector}[{checksum}]");
 
     // Set up state.
     let mut sector_sum: u32 = 0;
 
     // Read strings from the input file and process them.
-    for l in aoc::input_lines() {
-        let parts = room_pattern
-            .captures(&l)
-            .expect("main: could not match line");
-        let room_name = parts.get(1).expect("main: could not find room name").as_str();
-        let computed_sum = name_checksum(room_name);
-        let given_sum = parts.get(3).expect("main: could not find checksum").as_str();
+    let rooms = aoc::parse_lines(aoc::input_lines(), |l| room_pattern.parse_line(l));
+    for room in rooms {
+        let (room_name, sector_id, given_sum): (String, u32, String) =
+            room.unwrap_or_else(|e| panic!("{}", e));
+        let computed_sum = name_checksum(&room_name);
         if computed_sum != given_sum {
             continue;
         }
-        let sector_id = parts
-            .get(2)
-            .expect("main: could not find sector id")
-            .as_str()
-            .parse()
-            .expect("main: could not parse sector id");
 
         // Handle part 2 by printing all the decryptions.
         if decrypt {
-            println!("{} {}", name_decrypt(room_name, sector_id), sector_id);
+            println!("{} {}", name_decrypt(&room_name, sector_id), sector_id);
         }
         sector_sum += sector_id;
     }
@@ -11,9 +11,8 @@
 use std::io::*;
 
 extern crate aoc;
-extern crate md5;
 
-use md5::{Md5, Digest};
+use aoc::{HashKind, NybbleView, PuzzleHasher, SeededHasher};
 
 /// Pile up the password byte array into a string and return
 /// to the start of the line and show it.
@@ -25,50 +24,43 @@ pub fn cinema_string(marquee: &[char]) {
         .expect("cinema_string: could not flush stdout");
 }
 
-/// Find the password matching the room code, using the
-/// algorithm of part 2 of the problem if "positional" is
-/// true.
-pub fn main() {
-    // Get the input stuff figured out.
-    let part = aoc::get_part();
-    let positional = part == 2;
-    let lines = aoc::input_lines().collect::<Vec<String>>();
-    assert!(lines.len() == 1);
-    let room_code = &lines[0];
-
+/// Find the password matching the room code, hashing
+/// with `H` and looking for hashes starting with `nzeros`
+/// zero digits, using the algorithm of part 2 of the
+/// problem if `positional` is true. If `show` is true,
+/// display the password as it is found.
+fn password<H: PuzzleHasher>(
+    room_code: &str,
+    positional: bool,
+    nzeros: usize,
+    show: bool,
+) -> String {
     // Set up the password storage.
     let mut password = ['.'; 8];
-    cinema_string(&password);
+    if show {
+        cinema_string(&password);
+    }
 
     // Set up the rest of the state.
-    let mut hasher = Md5::new();
-    let prefix = room_code.as_bytes();
-    hasher.update(prefix);
+    let hasher = SeededHasher::<H>::with_prefix(room_code.as_bytes());
     let mut count = 0;
 
-    // This loop should never finish.
-    for i in 0..std::usize::MAX {
-        // Copy the partially-run hasher to avoid rehashing.
-        let mut hasher = hasher.clone();
-
-        // Get the current hash.
-        hasher.update(i.to_string().as_bytes());
-        let output = hasher.finalize();
+    // Hashes whose first `nzeros` hex digits are zero come
+    // back from the worker threads in index order, so
+    // earlier indices always win position collisions. This
+    // loop should never finish.
+    for (_, output) in aoc::mine_zeros(&hasher, nzeros) {
+        let nybbles = NybbleView::new(output.as_ref());
 
-        // If the first five hex digits are not zero, it's uninteresting.
-        if output[0] != 0 || output[1] != 0 || (output[2] >> 4) != 0 {
-            continue;
-        }
-
-        // Check for digit six in bounds.
-        let digit_six = (output[2] & 0xf) as usize;
+        // Check for the digit after the zeros in bounds.
+        let digit_six = nybbles.get(nzeros) as usize;
         if positional && digit_six >= password.len() {
             continue;
         }
 
         // Compute the current password position and digit.
         let (posn, digit) = if positional {
-            (digit_six, (output[3] >> 4) as usize)
+            (digit_six, nybbles.get(nzeros + 1) as usize)
         } else {
             (count, digit_six)
         };
@@ -80,14 +72,82 @@ pub fn main() {
 
         // Update and display the password.
         password[posn] = aoc::hex_digit(digit as u8);
-        cinema_string(&password);
+        if show {
+            cinema_string(&password);
+        }
 
         // If we have all the characters, it's over.
         count += 1;
         if count >= password.len() {
-            println!();
-            return;
+            if show {
+                println!();
+            }
+            return password.iter().collect();
         }
     }
     panic!("ran out of hashes");
 }
+
+/// Find the password matching the room code, using the
+/// algorithm of part 2 of the problem if "positional" is
+/// true. The hash is chosen by `AOC_HASH`.
+pub fn main() {
+    // Get the input stuff figured out.
+    let part = aoc::get_part();
+    let positional = part == 2;
+    let lines = aoc::input_lines().collect::<Vec<String>>();
+    assert!(lines.len() == 1);
+    let room_code = &lines[0];
+
+    aoc::with_hasher!(
+        HashKind::from_env(),
+        password(room_code, positional, 5, true)
+    );
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use aoc::FastHash;
+
+    /// Password found the slow way, through hex strings.
+    fn naive_password(room_code: &str, positional: bool, nzeros: usize) -> String {
+        let zeros = "0".repeat(nzeros);
+        let mut password = ['.'; 8];
+        let mut count = 0;
+        for i in 0.. {
+            let text = format!("{}{}", room_code, i);
+            let hex = aoc::hex_string(&FastHash::digest(text.as_bytes()));
+            if !hex.starts_with(&zeros) {
+                continue;
+            }
+            let hex = hex.as_bytes();
+            let (posn, digit) = if positional {
+                match (hex[nzeros] as char).to_digit(16) {
+                    Some(p) if p < 8 => (p as usize, hex[nzeros + 1] as char),
+                    _ => continue,
+                }
+            } else {
+                (count, hex[nzeros] as char)
+            };
+            if password[posn] == '.' {
+                password[posn] = digit;
+                count += 1;
+                if count == 8 {
+                    break;
+                }
+            }
+        }
+        password.iter().collect()
+    }
+
+    #[test]
+    fn test_password_fast() {
+        for positional in [false, true] {
+            assert_eq!(
+                password::<FastHash>("abc", positional, 3, false),
+                naive_password("abc", positional, 3)
+            );
+        }
+    }
+}
@@ -9,116 +9,56 @@
 const TRACING: bool = false;
 
 extern crate aoc;
-#[macro_use]
-extern crate lazy_static;
-extern crate regex;
-extern crate captures_at;
 
-use captures_at::CapturesAtExt;
-
-// Textual patterns for instructions.
-lazy_static! {
-    static ref RECT_PATTERN: regex::Regex =
-        regex::Regex::new(r"^rect (\d+)x(\d+)$").expect("could not compile rect pattern");
-    static ref ROTATE_ROW_PATTERN: regex::Regex =
-        regex::Regex::new(r"^rotate row y=(\d+) by (\d+)$").expect("could not compile row pattern");
-    static ref ROTATE_COLUMN_PATTERN: regex::Regex =
-        regex::Regex::new(r"^rotate column x=(\d+) by (\d+)$")
-            .expect("could not compile column pattern");
+/// Screen instruction.
+#[derive(aoc::FromLine)]
+enum Insn {
+    /// Turn on a rectangle at the top left.
+    #[pattern("rect {}x{}")]
+    Rect(usize, usize),
+    /// Rotate a row right.
+    #[pattern("rotate row y={} by {}")]
+    RotateRow(usize, usize),
+    /// Rotate a column down.
+    #[pattern("rotate column x={} by {}")]
+    RotateColumn(usize, usize),
 }
 
-/// Try for instruction to draw a rectangle. Returns true
-/// iff successful.
-fn insn_rect(insn: &str, m: &mut [Vec<char>]) -> bool {
-    match (*self::RECT_PATTERN).captures(insn) {
-        None => return false,
-        Some(parts) => {
-            // Parse arguments.
-            let x: usize = parts
-                .at(1)
-                .expect("insn_rect: could not find x")
-                .parse()
-                .expect("insn_rect: could not parse x");
-            let y: usize = parts
-                .at(2)
-                .expect("insn_rect: could not find y")
-                .parse()
-                .expect("insn_rect: could not parse y");
-
-            // Fill rectangle.
-            #[allow(clippy::needless_range_loop)]
-            for i in 0..x {
-                for j in 0..y {
-                    m[i][j] = '#';
-                }
-            }
+/// Draw an `x` by `y` rectangle.
+fn insn_rect(x: usize, y: usize, m: &mut [Vec<char>]) {
+    // Fill rectangle.
+    #[allow(clippy::needless_range_loop)]
+    for i in 0..x {
+        for j in 0..y {
+            m[i][j] = '#';
         }
-    };
-    true
+    }
 }
 
-/// Try for instruction to rotate a row right. Returns true
-/// iff successful.
-fn insn_rotate_row(insn: &str, m: &mut [Vec<char>]) -> bool {
-    match (*self::ROTATE_ROW_PATTERN).captures(insn) {
-        None => return false,
-        Some(parts) => {
-            // Parse arguments.
-            let y: usize = parts
-                .at(1)
-                .expect("insn_rotate_row: could not find y")
-                .parse()
-                .expect("insn_rotate_row: could not parse y");
-            let n: usize = parts
-                .at(2)
-                .expect("insn_rotate_row: could not find n")
-                .parse()
-                .expect("insn_rotate_row: could not parse n");
-
-            // Rotate row.
-            let d = m.len();
-            for _ in 0..n {
-                let tmp = m[d - 1][y];
-                for x in (1..d).rev() {
-                    m[x][y] = m[x - 1][y];
-                }
-                m[0][y] = tmp;
-            }
+/// Rotate row `y` right by `n`.
+fn insn_rotate_row(y: usize, n: usize, m: &mut [Vec<char>]) {
+    // Rotate row.
+    let d = m.len();
+    for _ in 0..n {
+        let tmp = m[d - 1][y];
+        for x in (1..d).rev() {
+            m[x][y] = m[x - 1][y];
         }
-    };
-    true
+        m[0][y] = tmp;
+    }
 }
 
-/// Try for instruction to rotate a column down. Returns
-/// true iff successful.
-fn insn_rotate_column(insn: &str, m: &mut [Vec<char>]) -> bool {
-    match (*self::ROTATE_COLUMN_PATTERN).captures(insn) {
-        None => return false,
-        Some(parts) => {
-            // Parse arguments.
-            let x: usize = parts
-                .at(1)
-                .expect("insn_rotate_column: could not find x")
-                .parse()
-                .expect("insn_rotate_column: could not parse x");
-            let n: usize = parts
-                .at(2)
-                .expect("insn_rotate_column: could not find n")
-                .parse()
-                .expect("insn_rotate_column: could not parse n");
-
-            // Rotate column.
-            let d = m[0].len();
-            for _ in 0..n {
-                let tmp = m[x][d - 1];
-                for y in (1..d).rev() {
-                    m[x][y] = m[x][y - 1];
-                }
-                m[x][0] = tmp;
-            }
+/// Rotate column `x` down by `n`.
+fn insn_rotate_column(x: usize, n: usize, m: &mut [Vec<char>]) {
+    // Rotate column.
+    let d = m[0].len();
+    for _ in 0..n {
+        let tmp = m[x][d - 1];
+        for y in (1..d).rev() {
+            m[x][y] = m[x][y - 1];
         }
-    };
-    true
+        m[x][0] = tmp;
+    }
 }
 
 /// Display the given screen.
@@ -148,26 +88,18 @@ fn main() {
         v.resize(y_size, '.');
         m.push(v);
     }
-    let insns = [insn_rect, insn_rotate_column, insn_rotate_row];
 
     // Read strings from the input file and process them.
-    for l in aoc::input_lines() {
-        // Search through the instructions until finding one
-        // that works.
-        let mut processed = false;
-        for f in &insns {
-            if f(&l, &mut m) {
-                processed = true;
-                if TRACING {
-                    println!();
-                    display(&m);
-                };
-                break;
-            }
-        }
-        if !processed {
-            panic!("undentified instruction");
+    for result in aoc::parse_lines(aoc::input_lines(), str::parse) {
+        match result.unwrap_or_else(|e| panic!("{}", e)) {
+            Insn::Rect(x, y) => insn_rect(x, y, &mut m),
+            Insn::RotateRow(y, n) => insn_rotate_row(y, n, &mut m),
+            Insn::RotateColumn(x, n) => insn_rotate_column(x, n, &mut m),
         }
+        if TRACING {
+            println!();
+            display(&m);
+        };
     }
 
     // Count up and report the on pixels.
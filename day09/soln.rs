@@ -5,65 +5,71 @@
 
 //! Advent of Code Day 9.
 
-use std::io;
-use std::io::prelude::*;
+use std::io::Read;
 use std::str;
 
 extern crate aoc;
 
-/// Walk a string of bytes, returning the length of its
+use aoc::ByteReader;
+
+/// Parse the body of a "(<replen>x<repcount>)" marker whose
+/// opening parenthesis has been consumed.
+fn read_marker<R: Read>(input: &mut ByteReader<R>) -> (u64, u64) {
+    let target = input
+        .read_until(b')')
+        .expect("read_marker: unterminated marker");
+    let target: &str = str::from_utf8(&target).expect("read_marker: invalid utf8 in target");
+    let coords = target.split('x').collect::<Vec<&str>>();
+    assert!(coords.len() == 2);
+    let replen = coords[0]
+        .parse()
+        .expect("read_marker: could not parse replen");
+    let repcount = coords[1]
+        .parse()
+        .expect("read_marker: could not parse repcount");
+    (replen, repcount)
+}
+
+/// Walk the next `limit` bytes of the input (or the rest
+/// of it, if shorter), returning the length of their
 /// expansion. If recurse is true, include the length of
 /// recursive subexpansions as new patterns are encountered.
-fn parse_expansion(pat: &[u8], recurse: bool) -> u64 {
+fn parse_expansion<R: Read>(input: &mut ByteReader<R>, limit: u64, recurse: bool) -> u64 {
     // Set up state.
-    let nchars = pat.len();
+    let end = input.offset().saturating_add(limit);
     let mut nemit = 0u64;
-    let mut i = 0;
 
     // Walk over the characters.
-    while i < nchars {
-        match (*pat)[i] as char {
+    while input.offset() < end {
+        let Some(c) = input.next() else {
+            break;
+        };
+        match c as char {
             '(' => {
-                // Pattern start found. First find the end.
-                let mut end = i;
-                while (*pat)[end] as char != ')' {
-                    end += 1;
-                }
-
-                // Grab the "coords" out of the pattern.
-                let target: &str = str::from_utf8(&(*pat)[i + 1..end])
-                    .expect("parse_expansion: invalid utf8 in target");
-                let coords = target.split('x').collect::<Vec<&str>>();
-                assert!(coords.len() == 2);
-                let replen: usize = coords[0]
-                    .parse()
-                    .expect("parse_expansion: could not parse replen");
-                let repcount: usize = coords[1]
-                    .parse()
-                    .expect("parse_expansion: could not parse repcount");
-
-                // Advance over the pattern.
-                i = end + 1;
+                // Pattern start found. Grab the "coords".
+                let (replen, repcount) = read_marker(input);
+                assert!(
+                    input.offset().saturating_add(replen) <= end,
+                    "parse_expansion: marker overruns its target"
+                );
 
-                // Process the target text.
+                // Process the target text, advancing over it.
                 if recurse {
-                    let subemit = parse_expansion(&(*pat)[i..i + replen], true);
-                    nemit += subemit * repcount as u64;
+                    let subemit = parse_expansion(input, replen, true);
+                    nemit += subemit * repcount;
                 } else {
-                    nemit += replen as u64 * repcount as u64;
+                    let skipped = input.skip_bytes(replen);
+                    assert!(skipped == replen, "parse_expansion: short target");
+                    nemit += replen * repcount;
                 };
-
-                // Advance over the target text.
-                i += replen;
             }
             '\n' => {
                 // A newline at the end is harmless.
-                assert!(i == nchars - 1);
+                assert!(input.peek().is_none());
                 break;
             }
             _ => {
                 // Just emit ordinary characters.
-                i += 1;
                 nemit += 1;
             }
         }
@@ -72,17 +78,58 @@ fn parse_expansion(pat: &[u8], recurse: bool) -> u64 {
     nemit
 }
 
-// Expand the top-level. XXX We first read the whole file
-// into memory, because it's easier, and because Rust has no
-// way to do better, really.  XXX If Unicode were an issue,
-// we should really transform the vector of bytes into a
-// vector of chars before passing it.
+// Expand the top-level, streaming the input so that it
+// can be arbitrarily large. XXX If Unicode were an issue,
+// we would need to decode chars rather than walk bytes.
 pub fn main() {
     let part = aoc::get_part();
-    let mut chars = Vec::new();
-    let _ = io::stdin()
-        .read_to_end(&mut chars)
-        .expect("could not read all of stdin");
-    let nemit = parse_expansion(&chars, part == 2);
+    let mut input = aoc::input_byte_reader();
+    let nemit = parse_expansion(&mut input, u64::MAX, part == 2);
     println!("{}", nemit);
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Length of the expansion of `text`.
+    fn expansion(text: &[u8], recurse: bool) -> u64 {
+        parse_expansion(&mut ByteReader::new(text), u64::MAX, recurse)
+    }
+
+    #[test]
+    fn test_part1() {
+        let examples: [(&[u8], u64); 6] = [
+            (b"ADVENT", 6),
+            (b"A(1x5)BC", 7),
+            (b"(3x3)XYZ", 9),
+            (b"A(2x2)BCD(2x2)EFG", 11),
+            (b"(6x1)(1x3)A", 6),
+            (b"X(8x2)(3x3)ABCY", 18),
+        ];
+        for (text, len) in examples {
+            assert_eq!(expansion(text, false), len);
+        }
+    }
+
+    #[test]
+    fn test_part2() {
+        let examples: [(&[u8], u64); 4] = [
+            (b"(3x3)XYZ", 9),
+            (b"X(8x2)(3x3)ABCY", 20),
+            (b"(27x12)(20x12)(13x14)(7x10)(1x12)A", 241920),
+            (
+                b"(25x3)(3x3)ABC(2x3)XY(5x2)PQRSTX(18x9)(3x2)TWO(5x7)SEVEN",
+                445,
+            ),
+        ];
+        for (text, len) in examples {
+            assert_eq!(expansion(text, true), len);
+        }
+    }
+
+    #[test]
+    fn test_trailing_newline() {
+        assert_eq!(expansion(b"A(1x5)BC\n", false), 7);
+    }
+}
@@ -14,78 +14,34 @@ const FAILURE_REPORT: bool = false;
 use std::cmp::*;
 use std::collections::HashSet;
 
-#[macro_use]
-extern crate lazy_static;
 extern crate aoc;
-extern crate captures_at;
-extern crate regex;
-
-use captures_at::CapturesAtExt;
-
-// Instruction patterns.
-lazy_static! {
-    static ref VALUE_PAT: regex::Regex = regex::Regex::new(r"^value (\d+) goes to (.+)$")
-        .expect("could not compile regex for value");
-    static ref COMPARE_PAT: regex::Regex =
-        regex::Regex::new(r"^bot (\d+) gives low to (.+) and high to (.+)$")
-            .expect("could not compile regex for compare");
-    static ref BOT_PAT: regex::Regex =
-        regex::Regex::new(r"^bot (\d+)$").expect("could not compile regex for bot");
-    static ref OUTPUT_PAT: regex::Regex =
-        regex::Regex::new(r"^output (\d+)$").expect("could not compile regex for output");
-}
 
 /// Instruction destination.
+#[derive(aoc::FromLine)]
 enum Dest {
     /// Destination is given bot.
+    #[pattern("bot {}")]
     Bot(usize),
     /// Destination is given output.
+    #[pattern("output {}")]
     Output(usize),
 }
 
 use self::Dest::*;
 
 /// Instruction.
+#[derive(aoc::FromLine)]
 enum Insn {
     /// Value(<value>, <dest>)
+    #[pattern("value {} goes to {}")]
     Value(usize, Dest),
     /// Compare(<robot>, <lowdest>, <highdest>)
+    #[pattern("bot {} gives low to {} and high to {}")]
     Compare(usize, Dest, Dest),
 }
 
 use self::Insn::*;
 
-/// Parse a destination description string to a dest.
-fn parse_dest(desc: &str) -> Dest {
-    // Try a bot description.
-    match (*self::BOT_PAT).captures(desc) {
-        None => (),
-        Some(parts) => {
-            return Bot(parts
-                .at(1)
-                .expect("parse_dest: could not find dest bot")
-                .parse()
-                .expect("parse_dest: could not parse dest bot"));
-        }
-    };
-
-    // Try an output description.
-    match (*self::OUTPUT_PAT).captures(desc) {
-        None => (),
-        Some(parts) => {
-            return Output(
-                parts
-                    .at(1)
-                    .expect("parse_dest: could not find dest output")
-                    .parse()
-                    .expect("parse_dest: could not parse dest output"),
-            );
-        }
-    };
-
-    panic!("unrecognized dest");
-}
-
 /// There's a lot here. General strategy is to compute the
 /// transitive closure of the value instructions over all
 /// possible bot processing. It turns out that in the
@@ -108,7 +64,7 @@ pub fn main() {
             .expect("main: could not parse high target");
         Some((target_low, target_high))
     } else {
-        assert!(target.len() == 0);
+        assert!(target.is_empty());
         None
     };
 
@@ -119,64 +75,26 @@ pub fn main() {
     let mut max_output = 0;
 
     // Read strings from the input file and process them.
-    for l in aoc::input_lines() {
-        // Try the value instruction.
-        match (*self::VALUE_PAT).captures(&l) {
-            None => (),
-            Some(parts) => {
-                // Parse and add to the instruction table.
-                let value_part = parts
-                    .at(1)
-                    .expect("main: could not find value")
-                    .parse()
-                    .expect("main: could not parse value");
-                let dest_part = parse_dest(parts.at(2).expect("main: could not find dest"));
-                match dest_part {
-                    Bot(b) => {
-                        max_bot = max(b, max_bot);
-                    }
-                    Output(q) => {
-                        max_output = max(q, max_output);
-                    }
-                };
-                insns.push(Value(value_part, dest_part));
-                continue;
+    for insn in aoc::parse_lines(aoc::input_lines(), str::parse::<Insn>) {
+        let insn = insn.unwrap_or_else(|e| panic!("{}", e));
+        let dests = match insn {
+            Value(_, ref dest) => vec![dest],
+            Compare(bot, ref low, ref high) => {
+                max_bot = max(bot, max_bot);
+                vec![low, high]
             }
         };
-        // Try the compare instruction.
-        match (*self::COMPARE_PAT).captures(&l) {
-            None => (),
-            Some(parts) => {
-                // Parse and add to the instruction table.
-                let bot_part = parts
-                    .at(1)
-                    .expect("main: could not find bot")
-                    .parse()
-                    .expect("main: could not parse bot");
-                max_bot = max(bot_part, max_bot);
-                let dest_low_part = parse_dest(parts.at(2).expect("main: could not find low"));
-                match dest_low_part {
-                    Bot(b) => {
-                        max_bot = max(b, max_bot);
-                    }
-                    Output(q) => {
-                        max_output = max(q, max_output);
-                    }
-                };
-                let dest_high_part = parse_dest(parts.at(3).expect("main: could not find high"));
-                match dest_high_part {
-                    Bot(b) => {
-                        max_bot = max(b, max_bot);
-                    }
-                    Output(q) => {
-                        max_output = max(q, max_output);
-                    }
-                };
-                insns.push(Compare(bot_part, dest_low_part, dest_high_part));
-                continue;
-            }
-        };
-        panic!("unrecognized instruction");
+        for dest in dests {
+            match *dest {
+                Bot(b) => {
+                    max_bot = max(b, max_bot);
+                }
+                Output(q) => {
+                    max_output = max(q, max_output);
+                }
+            };
+        }
+        insns.push(insn);
     }
 
     // Set up the rest of the state. The holds table is used
@@ -253,7 +171,7 @@ pub fn main() {
                                     }
                                     Output(ql) => {
                                         let unchanged = outputs[ql] == Some(*vl);
-                                        assert!(outputs[ql] == None || unchanged);
+                                        assert!(outputs[ql].is_none() || unchanged);
                                         if !unchanged {
                                             outputs[ql] = Some(*vl);
                                             changed = true;
@@ -280,7 +198,7 @@ pub fn main() {
                                     }
                                     Output(qh) => {
                                         let unchanged = outputs[qh] == Some(*vh);
-                                        assert!(outputs[qh] == None || unchanged);
+                                        assert!(outputs[qh].is_none() || unchanged);
                                         if !unchanged {
                                             outputs[qh] = Some(*vh);
                                             changed = true;
@@ -305,12 +223,12 @@ pub fn main() {
         // Part 1 has definitely failed.
         println!("no soln found");
         if FAILURE_REPORT {
-            for b in 0..holds.len() {
+            for (b, hold) in holds.iter().enumerate() {
                 print!("{}:", b);
-                for v in &holds[b] {
+                for v in hold {
                     print!(" {}", *v);
                 }
-                println!("");
+                println!();
             }
         }
     } else {
@@ -323,9 +241,9 @@ pub fn main() {
                 // Part 2 has definitely failed.
                 println!("no solution found");
                 if FAILURE_REPORT {
-                    for q in 0..outputs.len() {
+                    for (q, output) in outputs.iter().enumerate() {
                         print!("{}: ", q);
-                        match outputs[q] {
+                        match output {
                             None => {
                                 println!("-");
                             }
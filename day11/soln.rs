@@ -20,6 +20,7 @@ use std::collections::BTreeSet;
 use std::iter::*;
 
 extern crate aoc;
+use aoc::Set;
 extern crate captures_at;
 use captures_at::CapturesAtExt;
 extern crate regex;
@@ -33,13 +34,17 @@ enum Dev {
     Chip(String),
 }
 
+/// Contents of a floor. Any `aoc::Set` of `Dev` that is
+/// also `Ord` will do.
+type Floor = BTreeSet<Dev>;
+
 /// Returns true iff the floor contents can be left together
 /// without incident. *Idea:* either there are no generators,
 /// or every chip is protected by its generator.
-fn contents_are_safe(contents: &BTreeSet<Dev>) -> bool {
+fn contents_are_safe(contents: &Floor) -> bool {
     let mut gens = BTreeSet::new();
     let mut chips = BTreeSet::new();
-    for c in contents.iter().cloned() {
+    for c in contents.elems() {
         match c {
             Dev::Gen(name) => gens.insert(name),
             Dev::Chip(name) => chips.insert(name),
@@ -54,12 +59,12 @@ struct State {
     /// Current floor.
     location: usize,
     /// Contents of floors.
-    floors: Vec<BTreeSet<Dev>>,
+    floors: Vec<Floor>,
 }
 
 impl State {
     /// Initial state for given problem.
-    fn start(floors: Vec<BTreeSet<Dev>>) -> Self {
+    fn start(floors: Vec<Floor>) -> Self {
         State {
             location: 0,
             floors,
@@ -83,7 +88,7 @@ impl State {
     ///
     /// If the traverse is legal, return the state resulting
     /// from these operations.
-    fn try_traverse(&self, dirn: isize, grab: &BTreeSet<Dev>) -> Option<State> {
+    fn try_traverse(&self, dirn: isize, grab: &[&Dev]) -> Option<State> {
         // Can't move off either end of the elevator shaft.
         let (src, dest) = match self.try_move(dirn) {
             Some(ns) => ns,
@@ -98,13 +103,19 @@ impl State {
         };
 
         // Can't move if we leave the source floor unsafe.
-        let new_src = self.floors[src].difference(grab).cloned().collect();
+        let mut new_src = self.floors[src].clone();
+        for &dev in grab {
+            new_src.remove(dev);
+        }
         if !contents_are_safe(&new_src) {
             return None;
         };
 
         // Can't move if we leave the destination floor unsafe.
-        let new_dest = self.floors[dest].union(grab).cloned().collect();
+        let mut new_dest = self.floors[dest].clone();
+        for &dev in grab {
+            new_dest.insert(dev.clone());
+        }
         if !contents_are_safe(&new_dest) {
             return None;
         };
@@ -125,6 +136,7 @@ impl State {
     fn traversals(&self) -> BTreeSet<State> {
         // Try moving both down and up.
         let mut ts = BTreeSet::new();
+        let here: Vec<&Dev> = self.floors[self.location].iter().collect();
         for dirn in &[-1, 1] {
             // Precheck for efficiency to avoid working
             // through all possible grabs on an illegal
@@ -134,7 +146,7 @@ impl State {
             };
             // Try all possible grabs for traversal in
             // given direction.
-            for grab in aoc::choose_le(&self.floors[self.location], 2) {
+            for grab in aoc::combinations_le(&here, 2) {
                 if let Some(t) = self.try_traverse(*dirn, &grab) {
                     assert!(ts.insert(t));
                 }
@@ -214,14 +226,14 @@ fn read_start_state() -> State {
     assert!(floors.len() == NFLOORS);
 
     // Set up the state.
-    let mut floors0: Vec<BTreeSet<Dev>> = Vec::new();
+    let mut floors0: Vec<Floor> = Vec::new();
     let mut floor = 0;
 
     // Walk over the target description, placing things as
     // specified.
     for target in aoc::input_lines() {
         // Start the new floor.
-        floors0.push(BTreeSet::new());
+        floors0.push(Floor::default());
 
         // Check for top floor (or greater).
         assert!(floor < NFLOORS);
@@ -242,17 +254,14 @@ fn read_start_state() -> State {
         // Add all the stuff on the floor.
         for parts in device_pat.captures_iter(&target) {
             // Get the device type and class.
-            let dev_desc = parts
-                .at(1)
-                .expect("main: could not find device description");
-            let dev_mat = String::from(
-                compatible_pat
-                    .captures(dev_desc)
-                    .expect("main: could not parse device description")
-                    .at(1)
-                    .expect("main: could not find device description"),
-            );
-            let dev_class = parts.at(2).expect("main: could not find device class");
+            let (dev_desc, dev_class): (String, String) = parts
+                .parse_all()
+                .unwrap_or_else(|e| panic!("main: bad device: {}", e));
+            let dev_mat: String = compatible_pat
+                .captures(&dev_desc)
+                .expect("main: could not parse device description")
+                .parse_at(1)
+                .unwrap_or_else(|e| panic!("main: bad device description: {}", e));
 
             // Build the device.
             let dev = if dev_class == "generator" {
@@ -284,56 +293,34 @@ fn test1() {
     let gen_lithium = Dev::Gen(lithium.clone());
     let mut state = State::start(vec![
         aoc::make_set(&[chip_hydrogen.clone(), chip_lithium.clone()]),
-        aoc::make_set(&[gen_hydrogen.clone()]),
-        aoc::make_set(&[gen_lithium.clone()]),
-        BTreeSet::new(),
+        aoc::make_set(std::slice::from_ref(&gen_hydrogen)),
+        aoc::make_set(std::slice::from_ref(&gen_lithium)),
+        Floor::default(),
     ]);
-    let steps: [(isize, BTreeSet<Dev>); 11] = [
-        (1, aoc::make_set(&[chip_hydrogen.clone()])),
-        (
-            1,
-            aoc::make_set(&[chip_hydrogen.clone(), gen_hydrogen.clone()]),
-        ),
-        (-1, aoc::make_set(&[chip_hydrogen.clone()])),
-        (-1, aoc::make_set(&[chip_hydrogen.clone()])),
-        (
-            1,
-            aoc::make_set(&[chip_hydrogen.clone(), chip_lithium.clone()]),
-        ),
-        (
-            1,
-            aoc::make_set(&[chip_hydrogen.clone(), chip_lithium.clone()]),
-        ),
-        (
-            1,
-            aoc::make_set(&[chip_hydrogen.clone(), chip_lithium.clone()]),
-        ),
-        (-1, aoc::make_set(&[chip_hydrogen.clone()])),
-        (
-            1,
-            aoc::make_set(&[gen_hydrogen.clone(), gen_lithium.clone()]),
-        ),
-        (-1, aoc::make_set(&[chip_lithium.clone()])),
-        (
-            1,
-            aoc::make_set(&[chip_hydrogen.clone(), chip_lithium.clone()]),
-        ),
+    let steps: [(isize, &[&Dev]); 11] = [
+        (1, &[&chip_hydrogen]),
+        (1, &[&chip_hydrogen, &gen_hydrogen]),
+        (-1, &[&chip_hydrogen]),
+        (-1, &[&chip_hydrogen]),
+        (1, &[&chip_hydrogen, &chip_lithium]),
+        (1, &[&chip_hydrogen, &chip_lithium]),
+        (1, &[&chip_hydrogen, &chip_lithium]),
+        (-1, &[&chip_hydrogen]),
+        (1, &[&gen_hydrogen, &gen_lithium]),
+        (-1, &[&chip_lithium]),
+        (1, &[&chip_hydrogen, &chip_lithium]),
     ];
-    for step in steps.iter() {
-        let (dirn, ref grab) = *step;
+    for &(dirn, grab) in steps.iter() {
         match state.try_traverse(dirn, grab) {
             Some(next_state) => {
                 state = next_state;
             }
             None => {
-                panic!(format!(
-                    "bad traverse {} {:?} in state {:?}",
-                    dirn, *grab, state
-                ));
+                panic!("bad traverse {} {:?} in state {:?}", dirn, grab, state);
             }
         };
     }
-    if !state.is_goal() {
+    if !aoc::SearchState::is_goal(&state, &()) {
         panic!("state {:?} is not goal\n", state);
     };
 }
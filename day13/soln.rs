@@ -5,8 +5,6 @@
 
 //! Advent of Code Day 13.
 
-use std::collections::HashSet;
-
 extern crate aoc;
 
 /// Return true if there is a wall at the given location.
@@ -16,69 +14,15 @@ fn is_wall(k: usize, (x, y): aoc::Point) -> bool {
     hc & 1 == 1
 }
 
-/// Data about the maze itself.
-struct Maze {
-    grid_box: aoc::GridBox,
-    goal: aoc::Point,
-    key: usize,
-}
-
-/// Location within the maze. Has to be wrapped
-/// so that traits can be implemented on it.
-#[derive(Clone, Eq, PartialEq, PartialOrd, Ord)]
-enum State {
-    Loc((usize, usize)),
-}
-use self::State::*;
-
-impl aoc::SearchState for State {
-    /// We do not use labels here.
-    type Label = ();
-
-    /// The maze data is the global data for this problem.
-    type Global = Maze;
-
-    /// We do not use labels here.
-    fn label(&self) {}
-
-    /// State-space neighbors iterator.
-    fn neighbors(&self, maze: &Maze) -> Vec<(usize, Box<State>)> {
-        let &Loc(loc) = self;
-        let mut result = Vec::new();
-        let nbs = aoc::Neighbors::new(maze.grid_box, loc);
-        for nb in nbs {
-            if is_wall(maze.key, nb) {
-                continue;
-            };
-            result.push((1usize, Box::new(Loc(nb))));
-        }
-        result
-    }
-
-    /// Goal state is part of maze data.
-    fn is_goal(&self, maze: &Maze) -> bool {
-        let &Loc(loc) = self;
-        loc == maze.goal
-    }
-
-    /// The heuristic assumes no walls.
-    fn hcost(&self, maze: &Maze) -> usize {
-        let &Loc(loc) = self;
-        aoc::manhattan_distance(loc, maze.goal)
-    }
+/// The office maze for the given key.
+fn office_maze(key: usize) -> aoc::ProceduralMaze {
+    aoc::ProceduralMaze::from_walls(move |loc| is_wall(key, loc))
 }
 
 /// Solve part 1. Strategy: A\* search.
-fn part1(key: usize, goal: (usize, usize)) -> usize {
-    // Set up problem state.
-    let maze = Maze {
-        key,
-        goal,
-        grid_box: aoc::GridBox::new_grid(),
-    };
-
-    // Run the A\* search.
-    match aoc::a_star(&maze, &Loc((1, 1)), false) {
+fn part1(key: usize, goal: aoc::Point) -> usize {
+    let maze = office_maze(key).with_goal(goal);
+    match aoc::a_star(&maze, &aoc::MazePosn((1, 1)), false) {
         None => panic!("no solution"),
         Some((dist, _)) => dist,
     }
@@ -87,40 +31,8 @@ fn part1(key: usize, goal: (usize, usize)) -> usize {
 /// Solve part 2. Strategy: flood fill with
 /// distance recording.
 fn part2(key: usize, max_g: usize) -> usize {
-    // Set up the state.
-    let grid_box = aoc::GridBox::new_grid();
-    let mut fringe = HashSet::new();
-    fringe.insert((1, 1));
-    let mut stop = HashSet::new();
-
-    // Keep flooding while there are new nodes to explore.
-    for _ in 0..max_g {
-        // Fringe for next round.
-        let mut new_fringe = HashSet::new();
-
-        // Explore existing fringe.
-        for loc in fringe {
-            for nb in aoc::Neighbors::new(grid_box, loc) {
-                // Do not run into walls.
-                if is_wall(key, nb) {
-                    continue;
-                };
-
-                // Do not revisit already-explored locations.
-                if stop.contains(&nb) {
-                    continue;
-                };
-
-                // Found a new location.
-                new_fringe.insert(nb);
-                stop.insert(nb);
-            }
-        }
-
-        // Update the fringe.
-        fringe = new_fringe;
-    }
-    stop.len()
+    let maze = office_maze(key);
+    aoc::flood_fill(&maze, &aoc::MazePosn((1, 1)), max_g).len()
 }
 
 /// Read the problem description and run the search.
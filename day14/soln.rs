@@ -6,84 +6,29 @@
 //! Advent of Code Day 14.
 
 extern crate aoc;
-extern crate md5;
 
-use md5::{Digest, Md5};
-
-/// "Stretched" hasher for part 2.
-struct StretchedMd5(Md5);
-
-impl StretchedMd5 {
-    /// Just box up a plain old hasher.
-    fn new() -> StretchedMd5 {
-        Self(Md5::new())
-    }
-
-    fn reset(&mut self) {
-        self.0.reset();
-    }
-
-    fn update(&mut self, bytes: &[u8]) {
-        self.0.update(bytes);
-    }
-
-    /// Run the underlying MD5 hasher 2017 times to
-    /// produce the stretched hash.
-    fn finalize_into(self, hash: &mut [u8; 16]) {
-        let mut out = self.0.finalize();
-        for _ in 0..2016 {
-            let mut h = Md5::new();
-            let s = aoc::hex_string(out.as_slice());
-            let out_hex = &(s.as_bytes());
-            h.update(out_hex);
-            h.finalize_into(&mut out);
-        }
-        hash.copy_from_slice(out.as_slice());
-    }
-}
-
-/// Look for `reps` consecutive repetitions of a `target`
-/// digit in a given `hash`. If `target` is `None`,
-/// repetitions of any digit count.
-fn has_repeat(hash: &[u8; 16], target: Option<u8>, reps: usize) -> Option<u8> {
-    // Split up the input bytes into digits.
-    let mut nybbles = [0u8; 32];
-    #[allow(clippy::needless_range_loop)]
-    for i in 0..16 {
-        let j = i << 1;
-        nybbles[j] = (hash[i] >> 4) & 0xf;
-        nybbles[j + 1] = hash[i] & 0xf;
-    }
-
-    // Look for repetitions of target. O(*mn*) where
-    // *m* is the size of the input and *n* is the
-    // desired repetition count.
-    for i in 0..32 - reps {
-        // Look for repetitions at position *i*.
-        // Implemented as a function to allow early return
-        // for convenience.
-        let has_reps = || {
-            // Identify or select starting value.
-            let b0 = match target {
-                Some(b) => b,
-                None => nybbles[i],
-            };
-
-            // Check for consecutive matches of value.
-            for j in 0..reps {
-                if nybbles[i + j] != b0 {
-                    return false;
-                }
-            }
-            true
-        };
-
-        // Wrap the answer.
-        if has_reps() {
-            return Some(nybbles[i]);
-        };
-    }
-    None
+use aoc::{has_repeat, HashKind, PuzzleHasher, SeededHasher};
+
+/// Default number of extra rounds of hashing for
+/// "stretched" hashes in part 2.
+const STRETCH: usize = 2016;
+
+/// Hash the key followed by `index`, then "stretch" the
+/// result by hashing its own hex digits `stretch` more
+/// times.
+fn stretched_hash<H: PuzzleHasher>(
+    hasher: &SeededHasher<H>,
+    index: usize,
+    stretch: usize,
+) -> H::Output {
+    let mut output = hasher.hash_index(index);
+    let mut hex = [0; 64];
+    let hex = &mut hex[..2 * output.as_ref().len()];
+    for _ in 0..stretch {
+        aoc::hex_encode_into(output.as_ref(), hex);
+        output = H::digest(hex);
+    }
+    output
 }
 
 #[cfg(test)]
@@ -92,10 +37,7 @@ mod test_has_repeat {
     use super::*;
 
     fn make_hash(i: usize, output: &mut [u8; 16]) {
-        let mut hasher = Md5::new();
-        hasher.update("abc".to_string().as_bytes());
-        hasher.update(i.to_string().as_bytes());
-        hasher.md_finalize_into(output);
+        *output = SeededHasher::new(b"abc").hash_index(i);
     }
 
     fn has_rep(i: usize, t: Option<u8>, n: usize) -> Option<u8> {
@@ -106,21 +48,24 @@ mod test_has_repeat {
 
     #[test]
     fn check_reps() {
-        assert!(has_rep(17, None, 3) == None);
-        assert!(has_rep(18, None, 3) == Some(0x8));
-        assert!(has_rep(19, Some(0x8), 3) == None);
-        assert!(has_rep(38, None, 3) == None);
-        assert!(has_rep(39, Some(0xe), 3) == Some(0xe));
-        assert!(has_rep(816, Some(0xe), 5) == Some(0xe));
-        assert!(has_rep(92, Some(0x9), 3) == Some(0x9));
-        assert!(has_rep(200, Some(0x9), 5) == Some(0x9));
+        assert!(has_rep(17, None, 3).is_none());
+        assert_eq!(has_rep(18, None, 3), Some(0x8));
+        assert!(has_rep(19, Some(0x8), 3).is_none());
+        assert!(has_rep(38, None, 3).is_none());
+        assert_eq!(has_rep(39, Some(0xe), 3), Some(0xe));
+        assert_eq!(has_rep(816, Some(0xe), 5), Some(0xe));
+        assert_eq!(has_rep(92, Some(0x9), 3), Some(0x9));
+        assert_eq!(has_rep(200, Some(0x9), 5), Some(0x9));
+    }
+
+    #[test]
+    fn check_run_at_end() {
+        // MD5("abc514") ends in "ccc", its only triple.
+        assert_eq!(has_rep(514, None, 3), Some(0xc));
     }
 
     fn make_stretched_hash(i: usize, output: &mut [u8; 16]) {
-        let mut hasher = super::StretchedMd5::new();
-        hasher.update("abc".to_string().as_bytes());
-        hasher.update(i.to_string().as_bytes());
-        hasher.finalize_into(output);
+        *output = stretched_hash(&SeededHasher::new(b"abc"), i, STRETCH);
     }
 
     fn has_rep_stretched(i: usize, t: Option<u8>, n: usize) -> Option<u8> {
@@ -140,90 +85,39 @@ mod test_has_repeat {
         if aoc::hex_string(&output) != "a107ff634856bb300138cac6568c0f24" {
             panic!("{}", aoc::hex_string(&output));
         };
-        assert!(has_rep_stretched(0, None, 3) == None);
+        assert!(has_rep_stretched(0, None, 3).is_none());
     }
 }
 
-pub trait MiniDigest {
-    fn md_new() -> Self;
-    fn md_update(&mut self, bytes: &[u8]);
-    fn md_reset(&mut self);
-    fn md_finalize_into(self, hash: &mut [u8; 16]);
-}
-
-impl MiniDigest for Md5 {
-    fn md_new() -> Self {
-        Self::new()
-    }
-    fn md_update(&mut self, bytes: &[u8]) {
-        self.update(bytes);
-    }
-    fn md_reset(&mut self) {
-        self.reset()
-    }
-    fn md_finalize_into(self, hash: &mut [u8; 16]) {
-        let result = self.finalize();
-        hash.copy_from_slice(result.as_slice());
-    }
-}
-
-impl MiniDigest for StretchedMd5 {
-    fn md_new() -> Self {
-        Self::new()
-    }
-    fn md_update(&mut self, bytes: &[u8]) {
-        self.update(bytes);
-    }
-    fn md_reset(&mut self) {
-        self.reset()
-    }
-    fn md_finalize_into(self, hash: &mut [u8; 16]) {
-        self.finalize_into(hash);
-    }
-}
-
-fn solve<T: MiniDigest>(k: &[u8]) {
-    // Set up state.
-    let mut n: isize = 64;
-    let mut output = [0; 16];
-    let mut hashbuf: Vec<[u8; 16]> = Vec::new();
+/// Number of following hashes to search for a key's
+/// confirming run of five.
+const LOOKAHEAD: usize = 1000;
+
+/// Return the index producing the 64th key for salt `k`,
+/// using hashes stretched by `stretch` extra rounds.
+fn find_key<H: PuzzleHasher>(k: &[u8], stretch: usize) -> usize {
+    // Set up state. Hashes are computed in the background
+    // somewhat past the lookahead window, and remembered
+    // until the window passes them by.
+    let mut n = 64;
+    let hasher = SeededHasher::<H>::with_prefix(k);
+    let mut hashes =
+        aoc::HashWindow::new(2 * LOOKAHEAD, move |i| stretched_hash(&hasher, i, stretch));
 
     // This loop should never finish.
-    for i in 0..std::usize::MAX {
-        // If hash is not already cached, cache it.
-        if i >= hashbuf.len() {
-            let mut hasher = T::md_new();
-            hasher.md_update(k);
-            hasher.md_update(i.to_string().as_bytes());
-            hasher.md_finalize_into(&mut output);
-            hashbuf.push(output);
-        } else {
-            output = hashbuf[i];
-        }
-
-        // If we've found a repetition, look ahead up to 1001
-        // steps for another of the same.
-        if let Some(rep) = has_repeat(&output, None, 3) {
-            let mut output = [0; 16];
-            for j in 1..1001 {
-                // If hash is not already cached, cache it.
-                if i + j >= hashbuf.len() {
-                    let mut hasher = T::md_new();
-                    hasher.md_update(k);
-                    hasher.md_update((i + j).to_string().as_bytes());
-                    hasher.md_finalize_into(&mut output);
-                    hashbuf.push(output);
-                } else {
-                    output = hashbuf[i + j];
-                };
+    for i in 0..usize::MAX {
+        hashes.advance(i);
 
+        // If we've found a repetition, look ahead up to
+        // `LOOKAHEAD` steps for another of the same.
+        if let Some(rep) = has_repeat(hashes.get(i).as_ref(), None, 3) {
+            for j in 1..=LOOKAHEAD {
                 // If we've found the second repeat, process it.
-                if let Some(xrep) = has_repeat(&output, Some(rep), 5) {
+                if let Some(xrep) = has_repeat(hashes.get(i + j).as_ref(), Some(rep), 5) {
                     assert!(xrep == rep);
                     n -= 1;
-                    if n == 1 {
-                        println!("{}", i);
-                        return;
+                    if n == 0 {
+                        return i;
                     };
                     break;
                 };
@@ -233,16 +127,80 @@ fn solve<T: MiniDigest>(k: &[u8]) {
     panic!("no solution found");
 }
 
-/// Run the problem.
+/// Run the problem. An optional second argument gives the
+/// number of extra rounds of key stretching, which defaults
+/// to none for part 1 and `STRETCH` for part 2. The hash
+/// is chosen by `AOC_HASH`.
 pub fn main() {
     let (part, args) = aoc::get_part_args();
-    assert!(args.len() == 1);
-    let k = &(args[0].as_bytes());
-    if part == 1 {
-        solve::<Md5>(k);
-    } else if part == 2 {
-        solve::<StretchedMd5>(k);
-    } else {
-        panic!("unknown part");
+    assert!(args.len() == 1 || args.len() == 2);
+    let k = args[0].as_bytes();
+    let stretch = match (part, args.get(1)) {
+        (1 | 2, Some(stretch)) => stretch.parse().expect("could not parse stretch"),
+        (1, None) => 0,
+        (2, None) => STRETCH,
+        _ => panic!("unknown part"),
     };
+    let index = aoc::with_hasher!(HashKind::from_env(), find_key(k, stretch));
+    println!("{}", index);
+}
+
+#[cfg(test)]
+mod test_find_key {
+
+    use super::*;
+    use aoc::FastHash;
+
+    /// Hash as the puzzle statement describes it, through
+    /// hex strings.
+    fn naive_hash(k: &str, i: usize, stretch: usize) -> String {
+        let mut hex = aoc::hex_string(&FastHash::digest(format!("{}{}", k, i).as_bytes()));
+        for _ in 0..stretch {
+            hex = aoc::hex_string(&FastHash::digest(hex.as_bytes()));
+        }
+        hex
+    }
+
+    /// First character repeated `n` times in a row in `s`.
+    fn naive_run(s: &str, n: usize) -> Option<char> {
+        let chars: Vec<char> = s.chars().collect();
+        chars
+            .windows(n)
+            .find(|w| w.iter().all(|&c| c == w[0]))
+            .map(|w| w[0])
+    }
+
+    /// Index of the 64th key, found the slow way.
+    fn naive_find_key(k: &str, stretch: usize) -> usize {
+        let mut hashes: Vec<String> = Vec::new();
+        let mut nkeys = 0;
+        for i in 0.. {
+            while hashes.len() <= i + LOOKAHEAD {
+                hashes.push(naive_hash(k, hashes.len(), stretch));
+            }
+            if let Some(c) = naive_run(&hashes[i], 3) {
+                let five: String = std::iter::repeat_n(c, 5).collect();
+                if hashes[i + 1..=i + LOOKAHEAD]
+                    .iter()
+                    .any(|h| h.contains(&five))
+                {
+                    nkeys += 1;
+                    if nkeys == 64 {
+                        return i;
+                    }
+                }
+            }
+        }
+        unreachable!()
+    }
+
+    #[test]
+    fn test_find_key_fast() {
+        for stretch in [0, 2] {
+            assert_eq!(
+                find_key::<FastHash>(b"abc", stretch),
+                naive_find_key("abc", stretch)
+            );
+        }
+    }
 }
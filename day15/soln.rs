@@ -6,47 +6,51 @@
 //! Advent of Code Day 15.
 
 extern crate aoc;
-extern crate captures_at;
-use captures_at::CapturesAtExt;
-extern crate regex;
+
+use aoc::{FromRecord, LineError, Record};
+
+/// Description of a disc.
+struct Disc {
+    /// Disc number, counting from 1.
+    number: usize,
+    /// Number of positions.
+    positions: isize,
+    /// Position at time 0.
+    start: isize,
+}
+
+impl FromRecord for Disc {
+    fn from_record(record: &Record) -> Result<Self, LineError> {
+        Ok(Disc {
+            number: record.get("disc")?,
+            positions: record.get("npos")?,
+            start: record.get("start")?,
+        })
+    }
+}
 
 /// Read the disc descriptions, returning
 /// a vector of positions/start pairs.
 fn read_discs() -> Vec<(isize, isize)> {
     // Set up the matching.
-    let disc_pat = regex::Regex::new(
-        r"^Disc #([0-9]+) has ([0-9]+) positions; at time=0, it is at position ([0-9]+).$",
-    )
-    .expect("main: could not compile disc pattern");
+    let disc_pat = aoc::LinePattern::new(
+        "Disc #{disc} has {npos} positions; at time=0, it is at position {start}.",
+    );
 
     // Set up the state.
     let mut discs = Vec::new();
 
     // Process the disc descriptions.
-    for (ndiscs, target) in aoc::input_lines().enumerate() {
+    let descs = aoc::parse_lines(aoc::input_lines(), |l| disc_pat.parse_line(l));
+    for (ndiscs, desc) in descs.enumerate() {
         // Parse the description.
-        let parts = disc_pat.captures(&target).expect("malformed line");
-        let disc_number: isize = parts
-            .at(1)
-            .expect("main: could not find disc number")
-            .parse()
-            .expect("main: could not parse disc number");
-        let disc_positions = parts
-            .at(2)
-            .expect("main: could not find disc positions")
-            .parse()
-            .expect("main: could not parse disc positions");
-        let disc_start = parts
-            .at(3)
-            .expect("main: could not find disc start")
-            .parse()
-            .expect("main: could not parse disc start");
-        if disc_number != ndiscs as isize + 1 {
+        let disc: Disc = desc.unwrap_or_else(|e| panic!("{}", e));
+        if disc.number != ndiscs + 1 {
             panic!("unexpected disc number");
         };
 
         // Save the disc info.
-        discs.push((disc_positions, disc_start));
+        discs.push((disc.positions, disc.start));
     }
     discs
 }
@@ -68,7 +72,7 @@ fn search(discs: &[(isize, isize)], t0: isize) -> bool {
 /// Solve the problem.
 pub fn main() {
     let discs = read_discs();
-    for t in 0..std::isize::MAX {
+    for t in 0..isize::MAX {
         if search(&discs, t) {
             println!("{}", t);
             return;
@@ -8,7 +8,9 @@
 extern crate aoc;
 
 /// Run one dragon-curve step and return the resulting
-/// string.
+/// string. This is the reference implementation for
+/// `dragon_checksum()`.
+#[cfg(test)]
 fn fill_step(src: String) -> String {
     let mut dst = src.clone();
     dst.push('0');
@@ -33,12 +35,14 @@ fn test_fill_step() {
         ("111100001010", "1111000010100101011110000"),
     ];
     for &(src, dst) in tests.iter() {
-        assert!(fill_step(src.to_string()) == dst.to_string());
+        assert!(fill_step(src.to_string()) == dst);
     }
 }
 
-/// Return the "checksum" of some data.
-pub fn checksum(mut sum: String) -> String {
+/// Return the "checksum" of some data. This is the
+/// reference implementation for `dragon_checksum()`.
+#[cfg(test)]
+fn checksum(mut sum: String) -> String {
     while sum.len() & 1 == 0 {
         let mut new_sum = String::new();
         let sum_chars = sum.chars().collect::<Vec<char>>();
@@ -59,7 +63,109 @@ pub fn checksum(mut sum: String) -> String {
 
 #[test]
 fn test_checksum() {
-    assert!(checksum("110010110100".to_string()) == "100".to_string());
+    assert!(checksum("110010110100".to_string()) == "100");
+}
+
+/// Number of ones among the first `n` joiner bits of the
+/// dragon curve: the bits `0010011...` that the fill puts
+/// between copies of the seed and its reverse complement.
+/// Joiner `k` (from 1) is one exactly when the odd part of
+/// `k` is `3` mod `4`, so count those for each power of two.
+fn joiner_ones(n: usize) -> usize {
+    let mut count = 0;
+    let mut m = n;
+    while m > 0 {
+        count += (m + 1) / 4;
+        m >>= 1;
+    }
+    count
+}
+
+/// The filled disk data, represented implicitly. The data
+/// is the seed `a` and its reverse complement `b`
+/// alternating, separated by joiner bits: `a j1 b j2 a j3 b
+/// ...`.
+struct Dragon {
+    /// Length of the seed.
+    len: usize,
+    /// `prefix_ones[i]` is the number of ones in the first
+    /// `i` bits of the seed.
+    prefix_ones: Vec<usize>,
+}
+
+impl Dragon {
+    /// Set up the dragon curve with the given seed.
+    fn new(seed: &aoc::BitRow) -> Self {
+        let mut prefix_ones = vec![0];
+        for b in seed.iter() {
+            prefix_ones.push(prefix_ones[prefix_ones.len() - 1] + b as usize);
+        }
+        Dragon {
+            len: seed.len(),
+            prefix_ones,
+        }
+    }
+
+    /// Number of ones in the first `n` bits of the data.
+    fn ones(&self, n: usize) -> usize {
+        let m = self.len;
+        let seed_ones = self.prefix_ones[m];
+        // Whole seed-plus-joiner segments, then a partial
+        // copy of the seed or its reverse complement.
+        let nsegs = n / (m + 1);
+        let rest = n % (m + 1);
+        let na = nsegs.div_ceil(2);
+        let nb = nsegs / 2;
+        let partial = if nsegs & 1 == 0 {
+            self.prefix_ones[rest]
+        } else {
+            // The first `rest` bits of the reverse complement
+            // are the complements of the last `rest` seed bits.
+            rest - (seed_ones - self.prefix_ones[m - rest])
+        };
+        na * seed_ones + nb * (m - seed_ones) + joiner_ones(nsegs) + partial
+    }
+}
+
+/// Return the checksum of the dragon-curve fill of `seed`
+/// to `fill` bits, without building the data. Each checksum
+/// digit comes from a block of data whose size is the
+/// largest power of two dividing `fill`. Repeated pairwise
+/// xnor of a block of size two or more is one exactly when
+/// the block has even parity; a block of size one is just
+/// its bit.
+fn dragon_checksum(seed: &aoc::BitRow, fill: usize) -> aoc::BitRow {
+    assert!(fill > 0, "empty fill");
+    let dragon = Dragon::new(seed);
+    assert!(dragon.len > 0, "empty seed");
+    let block = fill & fill.wrapping_neg();
+    let flip = if block > 1 { 1 } else { 0 };
+    (0..fill / block)
+        .map(|i| {
+            let parity = (dragon.ones((i + 1) * block) - dragon.ones(i * block)) & 1;
+            parity ^ flip == 1
+        })
+        .collect()
+}
+
+#[test]
+fn test_dragon_checksum() {
+    let seed = "10000".parse().unwrap();
+    assert_eq!(dragon_checksum(&seed, 20).to_string(), "01100");
+    for seed_len in 1..=6 {
+        for bits in 0..1 << seed_len {
+            let seed: aoc::BitRow = (0..seed_len).map(|i| bits & (1 << i) != 0).collect();
+            for fill in 1..200 {
+                let mut data = seed.to_string();
+                while data.len() < fill {
+                    data = fill_step(data);
+                }
+                let expected = checksum(data[0..fill].to_string());
+                let sum = dragon_checksum(&seed, fill).to_string();
+                assert_eq!(sum, expected, "{} {}", seed, fill);
+            }
+        }
+    }
 }
 
 /// Run the fill-and-checksum on the given inputs.
@@ -67,16 +173,8 @@ pub fn main() {
     let args = aoc::get_args();
     assert!(args.len() == 2);
     let fill: usize = args[0].parse().expect("could not parse fill");
-    let mut data = args[1].to_string();
-
-    // Fill to specified size or more.
-    while data.len() < fill {
-        data = fill_step(data);
-    }
-
-    // Truncate the fill to specified size.
-    let data = &data[0..fill].to_string();
-
-    // Checksum.
-    println!("{}", checksum(data.to_string()));
+    let seed: aoc::BitRow = args[1]
+        .parse()
+        .unwrap_or_else(|e| panic!("could not parse seed: {}", e));
+    println!("{}", dragon_checksum(&seed, fill));
 }
@@ -5,11 +5,10 @@
 
 //! Advent of Code Day 17.
 
-extern crate md5;
-use md5::{Md5, Digest};
-
 extern crate aoc;
 
+use aoc::{HashKind, NybbleView, PuzzleHasher, SeededHasher, Set};
+
 /// State of exploration.
 #[derive(Clone)]
 enum Explo {
@@ -25,44 +24,48 @@ use self::Explo::*;
 
 /// Store true in `doors` for each of up, down, left, right
 /// iff the given hasher shows that door open.
-fn open_doors(hasher0: &Md5, doors: &mut [bool; 4]) {
-    // Run a copy of the hasher (to terminate here).
-    let hasher = hasher0.clone();
-    let output = hasher.finalize();
-
-    // Match up nybbles of the hash with doors.
-    let nybbles = [
-        (output[0] >> 4) & 0xf,
-        output[0] & 0xf,
-        (output[1] >> 4) & 0xf,
-        output[1] & 0xf,
-    ];
-    for d in 0..4 {
-        if nybbles[d] >= 11 {
-            doors[d] = true;
-        } else {
-            doors[d] = false;
-        }
+fn open_doors<H: PuzzleHasher>(hasher0: &SeededHasher<H>, doors: &mut [bool; 4]) {
+    // Doors whose nybble is `b` through `f` are open.
+    let output = hasher0.hash(b"");
+    let open = NybbleView::new(output.as_ref()).first_at_least(4, 11);
+    for (d, door) in doors.iter_mut().enumerate() {
+        *door = open.contains(&d);
     }
 }
 
 #[test]
 fn test_open_doors() {
-    let mut hasher = Md5::new();
-    hasher.update("hijkl".as_bytes());
+    let hasher = SeededHasher::new("hijkl".as_bytes());
     let mut doors = [false; 4];
     open_doors(&hasher, &mut doors);
     assert!(doors == [true, true, true, false]);
 }
 
+#[test]
+fn test_open_doors_fast() {
+    // Compare against the puzzle statement's recipe: hex
+    // digits `b` through `f` of the hash of the passcode
+    // and path so far.
+    for path in ["", "U", "DR", "UUDDLRLR"] {
+        let hasher = SeededHasher::<aoc::FastHash>::with_prefix(b"hijkl").extend(path.as_bytes());
+        let mut doors = [false; 4];
+        open_doors(&hasher, &mut doors);
+        let mut text = b"hijkl".to_vec();
+        text.extend_from_slice(path.as_bytes());
+        let hex = aoc::hex_string(&aoc::FastHash::digest(&text));
+        let expected: Vec<bool> = hex.chars().take(4).map(|c| c >= 'b').collect();
+        assert_eq!(&doors[..], &expected[..]);
+    }
+}
+
 /// Depth-First search for a path to a given location. The
 /// goal position is `posn`, the path to this point is
 /// `path`. Search will be for a longest path if
 /// `limit` is `None`: otherwise it will be for the
 /// first-found path of depth `limit` or less.
-fn dfs(
+fn dfs<H: PuzzleHasher>(
     grid_box: &aoc::GridBox,
-    hasher0: &Md5,
+    hasher0: &SeededHasher<H>,
     limit: Option<usize>,
     posn: aoc::Point,
     path: String,
@@ -103,17 +106,10 @@ fn dfs(
             Some(next_loc) => {
                 // Call recursively to explore continuation in this
                 // direction.
-                let mut hasher = hasher0.clone();
-                hasher.update(&[dirn as u8]);
+                let hasher = hasher0.extend(&[dirn as u8]);
                 let mut next_path = path.clone();
                 next_path.push(dirn);
-                let subresult = dfs(
-                    grid_box,
-                    &hasher,
-                    limit.map(|l| l - 1),
-                    next_loc,
-                    next_path,
-                );
+                let subresult = dfs(grid_box, &hasher, limit.map(|l| l - 1), next_loc, next_path);
 
                 // Combine the subsearch result with the existing
                 // status to get an updated status.
@@ -159,15 +155,11 @@ fn dfs(
     result
 }
 
-/// Search for a solution.
-pub fn main() {
-    let (part, args) = aoc::get_part_args();
-    assert!(args.len() == 1);
-    let passcode = args[0].as_bytes();
-
+/// Search for a solution to the given part, hashing with
+/// `H`.
+fn solve<H: PuzzleHasher>(part: usize, passcode: &[u8]) {
     // Set up state.
-    let mut hasher0 = Md5::new();
-    hasher0.update(passcode);
+    let hasher0 = SeededHasher::<H>::with_prefix(passcode);
     let grid_box = aoc::GridBox::new(4, 4);
 
     // For part 2, do a single search for longest path.
@@ -190,7 +182,7 @@ pub fn main() {
     // For part 1, use Depth-First Iterative Deepening to
     // find a shortest path.
     assert!(part == 1);
-    for limit in 0..std::usize::MAX {
+    for limit in 0..usize::MAX {
         let result = dfs(&grid_box, &hasher0, Some(limit), (0, 0), "".to_string());
         match result {
             Completed(soln) => {
@@ -206,3 +198,12 @@ pub fn main() {
     }
     panic!("exploration too shallow");
 }
+
+/// Search for a solution, with the hash chosen by
+/// `AOC_HASH`.
+pub fn main() {
+    let (part, args) = aoc::get_part_args();
+    assert!(args.len() == 1);
+    let passcode = args[0].as_bytes();
+    aoc::with_hasher!(HashKind::from_env(), solve(part, passcode))
+}
@@ -10,95 +10,61 @@ const SHOW: bool = false;
 
 extern crate aoc;
 
-/// Propagate one triple from the CA row.
-fn ca_step(patv: &[bool]) -> bool {
-    assert!(patv.len() == 3);
-    let pat = (patv[0], patv[1], patv[2]);
-    #[allow(clippy::match_like_matches_macro)]
-    match pat {
-        (false, false, true) => false,
-        (true, false, false) => false,
-        (false, true, true) => false,
-        (true, true, false) => false,
-        _ => true,
-    }
-}
+/// Elementary CA rule on safe (`true`) tiles. A tile is a
+/// trap exactly when its left and right neighbors differ,
+/// so it is safe when they agree: patterns `000`, `010`,
+/// `101` and `111`.
+const RULE: u8 = 0b1010_0101;
 
-/// Propagate the given initial row to the specified total
-/// rows, counting `true` cells during the process.
-fn count_ca(row0: &Vec<bool>, nrows: usize) -> usize {
-    // Set up state.
-    let ncells = row0.len();
-    let mut count = 0;
-    let mut cur_row = (*row0).clone();
+/// Walls beyond either end of the row count as safe.
+const BOUNDARY: aoc::Boundary = aoc::Boundary::Fixed(true);
 
-    // Construct all rows (plus one extra that is thrown
-    // away).
-    for _ in 0..nrows {
+/// Propagate the given initial row to the specified total
+/// rows, yielding the count of `true` cells in each row.
+fn row_counts(row0: &aoc::BitRow, nrows: usize) -> impl Iterator<Item = usize> {
+    let mut cur_row = row0.clone();
+    let mut next_row = aoc::BitRow::new(row0.len());
+    (0..nrows).map(move |_| {
         if SHOW {
-            println!("{}", decode_row(&cur_row));
+            println!("{}", cur_row.render(SAFE, TRAP));
         };
+        let count = cur_row.count_ones();
+        cur_row.step_into(RULE, BOUNDARY, &mut next_row);
+        std::mem::swap(&mut cur_row, &mut next_row);
+        count
+    })
+}
 
-        // Count `true` cells in current row.
-        for b in &cur_row {
-            if *b {
-                count += 1;
-            }
-        }
-
-        // Start the next row.
-        let mut next_row = Vec::new();
-
-        // Propagate the leftmost cell.
-        let left_pat = [true, cur_row[0], cur_row[1]];
-        let cell = ca_step(&left_pat);
-        next_row.push(cell);
+/// Steps to spend looking for a cycle before giving up and
+/// just counting. Real inputs do not cycle soon, and the
+/// search steps the CA separately from the count.
+const CYCLE_BUDGET: usize = 1000;
 
-        // Propagate the internal cells.
-        for posn in 1..ncells - 1 {
-            let cell = ca_step(&cur_row[posn - 1..posn + 2]);
-            next_row.push(cell);
+/// Propagate the given initial row to the specified total
+/// rows, counting `true` cells during the process. If the
+/// rows repeat soon enough, count one trip around the cycle
+/// and multiply.
+fn count_ca(row0: &aoc::BitRow, nrows: usize) -> usize {
+    let step = |row: &aoc::BitRow| row.step(RULE, BOUNDARY);
+    match aoc::find_cycle_within(row0.clone(), step, nrows.min(CYCLE_BUDGET)) {
+        Some(cycle) if cycle.prefix + cycle.period < nrows => {
+            let counts: Vec<usize> = row_counts(row0, cycle.prefix + cycle.period).collect();
+            let (head, lap) = counts.split_at(cycle.prefix);
+            let nlaps = (nrows - cycle.prefix) / cycle.period;
+            let rest = (nrows - cycle.prefix) % cycle.period;
+            head.iter().sum::<usize>()
+                + nlaps * lap.iter().sum::<usize>()
+                + lap[..rest].iter().sum::<usize>()
         }
-
-        // Propagate the rightmost cell.
-        let right_pat = [cur_row[ncells - 2], cur_row[ncells - 1], true];
-        let cell = ca_step(&right_pat);
-        next_row.push(cell);
-
-        // Save the propagated row.
-        cur_row = next_row;
+        _ => row_counts(row0, nrows).sum(),
     }
-    count
 }
 
-/// Turn a row into a string for debugging.
-fn decode_row(row: &[bool]) -> String {
-    let mut result = String::new();
-    for b in row {
-        if *b {
-            result.push('.');
-        } else {
-            result.push('^');
-        }
-    }
-    result
-}
+/// Text for a safe tile.
+const SAFE: char = '.';
 
-/// Turn a string into a row for setup.
-fn encode_row(row: &str) -> Vec<bool> {
-    let mut result = Vec::new();
-    for c in row.chars() {
-        let e = match c {
-            '^' => false,
-            '.' => true,
-            _ => {
-                panic!("unexpected char in row");
-            }
-        };
-        result.push(e);
-    }
-    result
-}
+/// Text for a trap tile.
+const TRAP: char = '^';
 
 /// Run the prop-and-count.
 pub fn main() {
@@ -110,5 +76,7 @@ pub fn main() {
     if lines.next().is_some() {
         panic!("more than one row");
     };
-    println!("{}", count_ca(&encode_row(&row0), nrows));
+    let row0 = aoc::BitRow::parse_with(&row0, SAFE, TRAP)
+        .unwrap_or_else(|e| panic!("could not parse row: {}", e));
+    println!("{}", count_ca(&row0, nrows));
 }
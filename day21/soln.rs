@@ -10,49 +10,40 @@ const SHOW: bool = false;
 
 extern crate aoc;
 extern crate permutohedron;
-extern crate regex;
 
 use permutohedron::Heap;
 
 /// Instructions.
-#[derive(Debug)]
+#[derive(Debug, aoc::FromLine)]
 enum Insn {
     /// Swap at given indices.
+    #[pattern("swap position {} with position {}")]
     SwPos(usize, usize),
     /// Swap given characters.
+    #[pattern("swap letter {:char} with letter {:char}")]
     SwChar(char, char),
     /// Rotate left
+    #[pattern("rotate left {} step")]
+    #[pattern("rotate left {} steps")]
     RotL(usize),
     /// Rotate right by given amount.
+    #[pattern("rotate right {} step")]
+    #[pattern("rotate right {} steps")]
     RotR(usize),
     /// Rotate based on position of given character.
+    #[pattern("rotate based on position of letter {:char}")]
     RotPos(char),
     /// Reverse the range bounded by the given indices.
+    #[pattern("reverse positions {} through {}")]
     Rev(usize, usize),
     /// Move the character at the given source position
     /// to the given destination position.
+    #[pattern("move position {} to position {}")]
     Mov(usize, usize),
 }
 
 use self::Insn::*;
 
-/// If the given pattern matches the given target, return a
-/// vector of its submatches (parts).
-fn try_pat(pat: &regex::Regex, target: &str) -> Option<Vec<String>> {
-    match pat.captures(target) {
-        Some(parts) => {
-            let mut v = Vec::new();
-            let mut iter = parts.iter();
-            iter.next();
-            for p in iter {
-                v.push(p.expect("part did not match").as_str().to_string());
-            }
-            Some(v)
-        }
-        None => None,
-    }
-}
-
 /// Return the position of the given character in the given
 /// slice. The character must be present to win.
 fn find_char(c: char, v: &[char]) -> usize {
@@ -65,7 +56,7 @@ fn find_char(c: char, v: &[char]) -> usize {
 }
 
 /// Rotate the given slice to the left *r* times.
-fn rotate_left(r: usize, chars: &mut Vec<char>) {
+fn rotate_left(r: usize, chars: &mut [char]) {
     for _ in 0..r {
         let n = chars.len();
         let tmp = chars[0];
@@ -77,7 +68,7 @@ fn rotate_left(r: usize, chars: &mut Vec<char>) {
 }
 
 /// Rotate the given slice to the right *r* times.
-fn rotate_right(r: usize, chars: &mut Vec<char>) {
+fn rotate_right(r: usize, chars: &mut [char]) {
     for _ in 0..r {
         let n = chars.len();
         let tmp = chars[n - 1];
@@ -90,73 +81,15 @@ fn rotate_right(r: usize, chars: &mut Vec<char>) {
 
 /// Read the input instructions from `stdin`, parse and return them.
 fn read_insns() -> Vec<Insn> {
-    // Set up instruction patterns.
-    let swpos_pat = regex::Regex::new(r"^swap position ([0-9]+) with position ([0-9]+)$")
-        .expect("could not compile swpos pattern");
-    let swchar_pat = regex::Regex::new(r"^swap letter ([a-z]) with letter ([a-z])$")
-        .expect("could not compile swchar pattern");
-    let rot_pat = regex::Regex::new(r"^rotate (left|right) ([0-9+]) steps?$")
-        .expect("could not compile rot pattern");
-    let rotpos_pat = regex::Regex::new(r"^rotate based on position of letter ([a-z])$")
-        .expect("could not compile rotpos pattern");
-    let rev_pat = regex::Regex::new(r"^reverse positions ([0-9]+) through ([0-9]+)$")
-        .expect("could not compile rev pattern");
-    let mov_pat = regex::Regex::new(r"^move position ([0-9]+) to position ([0-9]+)$")
-        .expect("could not compile mov pattern");
-
-    // Set up state and process lines.
-    let mut insns = Vec::new();
-    for target in aoc::input_lines() {
+    let parse = |target: &str| {
         if SHOW {
             println!("{}", target);
         };
-
-        // Try to find and translate a matching instruction.
-        if let Some(args) = try_pat(&swpos_pat, &target) {
-            let p1 = args[0].parse::<usize>().unwrap();
-            let p2 = args[1].parse::<usize>().unwrap();
-            insns.push(SwPos(p1, p2));
-            continue;
-        };
-        if let Some(args) = try_pat(&swchar_pat, &target) {
-            let c1 = args[0].chars().next().expect("swchar: op1 error");
-            let c2 = args[1].chars().next().expect("swchar: op2 error");
-            insns.push(SwChar(c1, c2));
-            continue;
-        };
-        if let Some(args) = try_pat(&rot_pat, &target) {
-            let r = args[1].parse::<usize>().unwrap();
-            if args[0] == "left" {
-                insns.push(RotL(r));
-            } else if args[0] == "right" {
-                insns.push(RotR(r));
-            } else {
-                panic!("unknown rotation direction");
-            };
-            continue;
-        };
-        if let Some(args) = try_pat(&rotpos_pat, &target) {
-            let c = args[0].chars().next().expect("rotpos: op1 error");
-            insns.push(RotPos(c));
-            continue;
-        };
-        if let Some(args) = try_pat(&rev_pat, &target) {
-            let p1 = args[0].parse::<usize>().unwrap();
-            let p2 = args[1].parse::<usize>().unwrap();
-            insns.push(Rev(p1, p2));
-            continue;
-        };
-        if let Some(args) = try_pat(&mov_pat, &target) {
-            let p1 = args[0].parse::<usize>().unwrap();
-            let p2 = args[1].parse::<usize>().unwrap();
-            insns.push(Mov(p1, p2));
-            continue;
-        };
-
-        // Didn't work.
-        panic!("could not read instruction");
-    }
-    insns
+        target.parse()
+    };
+    aoc::parse_lines(aoc::input_lines(), parse)
+        .map(|insn| insn.unwrap_or_else(|e| panic!("{}", e)))
+        .collect()
 }
 
 /// Run the sequence `insns` of instructions on the
@@ -6,10 +6,8 @@
 // Advent of Code Day 22.
 
 use std::cmp::*;
-use std::collections::HashSet;
 
 extern crate aoc;
-extern crate regex;
 
 /// Turn on to show board for part 2.
 const SHOW: bool = false;
@@ -25,8 +23,8 @@ struct Usage {
 
 /// Board information for search.
 struct Board {
-    grid_box: aoc::GridBox,
-    tiles: HashSet<(usize, usize)>,
+    /// Which squares are tiles rather than walls.
+    tiles: aoc::Grid<bool>,
 }
 
 /// The game state is just the position of the
@@ -52,8 +50,8 @@ impl aoc::SearchState for State {
     /// Neighbors are valid tiles around blank.
     fn neighbors(&self, global: &Board) -> Vec<(usize, Box<Self>)> {
         let mut result = Vec::new();
-        for next_blank in global.grid_box.neighbors(self.blank) {
-            if !global.tiles.contains(&next_blank) {
+        for next_blank in global.tiles.neighbors(self.blank, aoc::VonNeumann) {
+            if !global.tiles[next_blank] {
                 continue;
             };
             let mut next_goal_data = self.goal_data;
@@ -83,88 +81,47 @@ impl aoc::SearchState for State {
     }
 }
 
-/// Try to match given regex, returning submatches if successful.
-fn try_pat(pat: &regex::Regex, target: &str) -> Option<Vec<String>> {
-    match pat.captures(target) {
-        Some(parts) => {
-            let mut v = Vec::new();
-            let mut iter = parts.iter();
-            iter.next();
-            for p in iter {
-                v.push(p.expect("part did not match").as_str().to_string());
-            }
-            Some(v)
-        }
-        None => None,
-    }
-}
-
 /// Read usages from `stdin` and return a vector with
 /// position and usage information.
 fn read_usages() -> Vec<(usize, usize, Usage)> {
-    // Set up state.
-    let usage_pat =
-        regex::Regex::new(r"^/dev/grid/node-x(\d+)-y(\d+) *\d+T *(\d+)T *(\d+)T *\d+%$")
-            .expect("could not compile usage pattern");
-    let mut usages = Vec::new();
+    // Set up state. Lines not describing a node, such as
+    // the `df` headers, are skipped.
+    let usage_pat = aoc::LinePattern::new("/dev/grid/node-x{x}-y{y} {_}T {used}T {avail}T {_}%");
+    let parse = |l: &str| usage_pat.parse_line(l);
 
     // Process each line.
-    for target in aoc::input_lines() {
-        if let Some(args) = try_pat(&usage_pat, &target) {
-            assert!(args.len() == 4);
-            let mut argv = [0usize; 4];
-            for i in 0..4 {
-                argv[i] = args[i].parse::<usize>().unwrap();
-            }
-            usages.push((
-                argv[0],
-                argv[1],
-                Usage {
-                    used: argv[2],
-                    avail: argv[3],
-                },
-            ));
-        };
-    }
-    usages
+    aoc::parse_lines(aoc::input_lines(), parse)
+        .skip_unmatched()
+        .map(|usage| {
+            let (x, y, used, avail) = usage.unwrap_or_else(|e| panic!("{}", e));
+            (x, y, Usage { used, avail })
+        })
+        .collect()
 }
 
 /// Use the obvious quadratic algorithm to test each pair
 /// For viability. Pairs are directed, so no need to divide
 /// by two or anything like that.
-fn count_viable_pairs(umap: &[Vec<Usage>]) -> usize {
-    // Set up state.
-    let len_x = umap.len();
-    let len_y = umap[0].len();
+fn count_viable_pairs(umap: &aoc::Grid<Usage>) -> usize {
     let mut viable_pairs = 0;
 
     // Loop over source coordinates.
-    for x0 in 0..len_x {
-        for y0 in 0..len_y {
-            // Get source use for later.
-            let used_a = umap[x0][y0].used;
+    for (p0, u0) in umap.iter() {
+        // Never pull from empty disc.
+        if u0.used == 0 {
+            continue;
+        };
 
-            // Never pull from empty disc.
-            if used_a == 0 {
+        // Loop over destination coordinates.
+        for (p1, u1) in umap.iter() {
+            // Source and dest must be different.
+            if p1 == p0 {
                 continue;
             };
 
-            // Loop over destination coordinates.
-            #[allow(clippy::needless_range_loop)]
-            for x1 in 0..len_x {
-                for y1 in 0..len_y {
-                    // Source and dest must be different.
-                    if x1 == x0 && y1 == y0 {
-                        continue;
-                    };
-
-                    // Get dest space, and bump count if source
-                    // fits.
-                    let avail_b = umap[x1][y1].avail;
-                    if avail_b >= used_a {
-                        viable_pairs += 1;
-                    }
-                }
+            // Bump count if source fits.
+            if u1.avail >= u0.used {
+                viable_pairs += 1;
             }
         }
     }
@@ -174,49 +131,32 @@ fn count_viable_pairs(umap: &[Vec<Usage>]) -> usize {
 /// Heuristically transform the given resource usage map
 /// into a sliding tile puzzle using the methodology
 /// detailed in the description of part 2. Return
-/// a starting state and a set of coordinates that
-/// are "wall squares".
-fn start_info(umap: &[Vec<Usage>]) -> (State, HashSet<(usize, usize)>) {
+/// a starting state and a grid marking the tile
+/// (non-wall) squares.
+fn start_info(umap: &aoc::Grid<Usage>) -> (State, aoc::Grid<bool>) {
     // Set up state.
-    let len_x = umap.len();
-    let len_y = umap[0].len();
-    let grid_box = aoc::GridBox::new(len_x, len_y);
-    let mut blank: Option<(usize, usize)> = None;
-    let mut tiles: HashSet<(usize, usize)> = HashSet::new();
+    let mut blank: Option<aoc::Point> = None;
+    let mut tiles = aoc::Grid::new(umap.width(), umap.height(), false);
 
     // Loop over source coordinates.
-    for x in 0..len_x {
-        for y in 0..len_y {
-            // An empty disc represents the blank. There can
-            // be only one.
-            if umap[x][y].used == 0 {
-                if blank.is_some() {
-                    panic!("two blanks");
-                };
-                blank = Some((x, y));
-                continue;
-            };
-
-            // Heuristically decide whether the given square is
-            // a tile or a wall. Block because early return.
-            let is_tile = || {
-                // Loop over immediate neighbors.
-                for (x0, y0) in grid_box.neighbors((x, y)) {
-                    // Heuristic: If this neighbor could not
-                    // accept the data if empty, it is a wall.
-                    let u = &umap[x0][y0];
-                    if umap[x][y].used > u.used + u.avail {
-                        return false;
-                    }
-                }
-                true
+    for (p, u) in umap.iter() {
+        // An empty disc represents the blank. There can
+        // be only one.
+        if u.used == 0 {
+            if blank.is_some() {
+                panic!("two blanks");
             };
+            blank = Some(p);
+            continue;
+        };
 
-            // Run the block.
-            if is_tile() {
-                tiles.insert((x, y));
-            };
-        }
+        // Heuristically decide whether the given square is
+        // a tile or a wall: if some neighbor could not
+        // accept the data if empty, it is a wall.
+        tiles[p] = umap.neighbors(p, aoc::VonNeumann).all(|n| {
+            let un = &umap[n];
+            u.used <= un.used + un.avail
+        });
     }
 
     // Check on the blank.
@@ -228,7 +168,7 @@ fn start_info(umap: &[Vec<Usage>]) -> (State, HashSet<(usize, usize)>) {
     // Return the new state and tiles.
     (
         State {
-            goal_data: (len_x - 1, 0),
+            goal_data: (umap.width() - 1, 0),
             blank,
         },
         tiles,
@@ -236,21 +176,19 @@ fn start_info(umap: &[Vec<Usage>]) -> (State, HashSet<(usize, usize)>) {
 }
 
 // Display the part 2 map for debugging.
-fn print_map(s: &State, tiles: &HashSet<(usize, usize)>, len_x: usize, len_y: usize) {
-    for y in 0..len_y {
-        for x in 0..len_x {
-            if (x, y) == s.blank {
-                print!("_");
-            } else if (x, y) == s.goal_data {
-                print!("G");
-            } else if tiles.contains(&(x, y)) {
-                print!(".");
-            } else {
-                print!("#");
-            };
+fn print_map(s: &State, tiles: &aoc::Grid<bool>) {
+    let map = tiles.render(|p, &tile| {
+        if p == s.blank {
+            '_'
+        } else if p == s.goal_data {
+            'G'
+        } else if tile {
+            '.'
+        } else {
+            '#'
         }
-        println!();
-    }
+    });
+    print!("{}", map);
 }
 
 // Do the appropriate search through the disc array.
@@ -274,12 +212,9 @@ pub fn main() {
     let len_y = max_y + 1;
 
     // Build the map.
-    let mut umap: Vec<Vec<Usage>> = Vec::new();
-    let mut row = Vec::new();
-    row.resize(len_y, Usage { used: 0, avail: 0 });
-    umap.resize(len_x, row);
+    let mut umap = aoc::Grid::new(len_x, len_y, Usage { used: 0, avail: 0 });
     for (x, y, u) in usages.iter().cloned() {
-        umap[x][y] = u;
+        umap[(x, y)] = u;
     }
 
     if part == 1 {
@@ -289,12 +224,9 @@ pub fn main() {
         // Solve the sliding tile puzzle.
         let (start, tiles) = start_info(&umap);
         if SHOW {
-            print_map(&start, &tiles, len_x, len_y);
-        };
-        let board = Board {
-            grid_box: aoc::GridBox::new_grid(),
-            tiles,
+            print_map(&start, &tiles);
         };
+        let board = Board { tiles };
         if let Some((g, _)) = aoc::a_star(&board, &start, false) {
             println!("{}", g);
         } else {
@@ -5,15 +5,18 @@
 
 //! Advent of Code Day 24.
 
-use std::collections::{BTreeSet, HashMap, HashSet};
-
 extern crate aoc;
 
+/// Set of visited goals. Any `aoc::Set` of `usize` that is
+/// also `Ord` will do.
+type Visited = aoc::BitSet64;
+
 /// Map to be explored.
 struct Map {
-    open: HashSet<aoc::Point>,
-    goals: HashMap<aoc::Point, u32>,
-    grid_box: aoc::GridBox,
+    /// Map squares: walls are `#`, goals are digits.
+    grid: aoc::Grid<char>,
+    /// Number of goals, including the start.
+    ngoals: usize,
     part: usize,
     start_posn: aoc::Point,
 }
@@ -24,7 +27,7 @@ struct State {
     /// Current position.
     posn: aoc::Point,
     /// Set of previously-visited positions.
-    visited: BTreeSet<u32>,
+    visited: Visited,
 }
 
 impl aoc::SearchState for State {
@@ -35,19 +38,17 @@ impl aoc::SearchState for State {
 
     fn neighbors(&self, global: &Self::Global) -> Vec<(usize, Box<Self>)> {
         let mut result = Vec::new();
-        for next_posn in global.grid_box.neighbors(self.posn) {
+        for next_posn in global.grid.neighbors(self.posn, aoc::VonNeumann) {
             // Can't go through walls.
-            if !global.open.contains(&next_posn) {
+            let c = global.grid[next_posn];
+            if c == '#' {
                 continue;
             };
 
             // Add to the visited list if needed.
-            let mut next_visited = self.visited.clone();
-            match global.goals.get(&next_posn) {
-                None => (),
-                Some(d) => {
-                    next_visited.insert(*d);
-                }
+            let mut next_visited = self.visited;
+            if let Some(d) = c.to_digit(10) {
+                aoc::Set::insert(&mut next_visited, d as usize);
             };
 
             // Push the neighbor state.
@@ -63,7 +64,7 @@ impl aoc::SearchState for State {
     /// Stop when everything has been visited,
     /// but in part 2 only when we then get home.
     fn is_goal(&self, global: &Map) -> bool {
-        self.visited.len() == global.goals.len()
+        aoc::Set::len(&self.visited) == global.ngoals
             && (global.part == 1 || self.posn == global.start_posn)
     }
 }
@@ -73,60 +74,29 @@ impl aoc::SearchState for State {
 pub fn main() {
     let part = aoc::get_part();
 
-    // Set up state.
-    let mut open: HashSet<aoc::Point> = HashSet::new();
-    let mut goals: HashMap<aoc::Point, u32> = HashMap::new();
-    let mut goal_labels: BTreeSet<u32> = BTreeSet::new();
-    let mut maybe_start: Option<aoc::Point> = None;
     // Process the map.
-    let mut x = 0;
-    let mut y = 0;
-    // XXX `x` and `y` are flipped when incrementing.
-    #[allow(clippy::explicit_counter_loop)]
-    for row in aoc::input_lines() {
-        for c in row.chars() {
-            match c {
-                '.' => {
-                    open.insert((x, y));
-                }
-                '#' => (),
-                dc if dc.is_ascii_digit() => {
-                    match dc.to_digit(10).unwrap() {
-                        0 => {
-                            // Location 0 is special.
-                            maybe_start = Some((x, y));
-                            open.insert((x, y));
-                        }
-                        d => {
-                            goals.insert((x, y), d);
-                            goal_labels.insert(d);
-                            open.insert((x, y));
-                        }
-                    }
-                }
-                _ => {
-                    panic!("bad char in input");
-                }
-            };
-            x += 1;
-        }
-        x = 0;
-        y += 1;
-    }
-    let start_posn = match maybe_start {
+    let grid = aoc::Grid::from_lines(aoc::input_lines(), |c| {
+        assert!(
+            c == '.' || c == '#' || c.is_ascii_digit(),
+            "bad char in input"
+        );
+        c
+    });
+    let ngoals = grid.iter().filter(|(_, c)| c.is_ascii_digit()).count();
+    // Location 0 is special.
+    let start_posn = match grid.find(|&c| c == '0') {
         Some(p) => p,
         None => panic!("no start position found"),
     };
     let map = Map {
-        open,
-        goals,
-        grid_box: aoc::GridBox::new_grid(),
+        grid,
+        ngoals,
         part,
         start_posn,
     };
     let start_state = State {
         posn: start_posn,
-        visited: BTreeSet::new(),
+        visited: aoc::make_set(&[0]),
     };
     match aoc::a_star(&map, &start_state, false) {
         Some((g, _)) => println!("{}", g),
@@ -23,7 +23,21 @@
 //! memory. (This doesn't matter for AoC 2016, since none of
 //! the A\* problems ask for a path.)
 //!
+//! `flood_fill()` finds the cost of reaching every state
+//! within a given cost of the start.
+//!
+//! For checking puzzle solutions, it is sometimes useful to
+//! know about more than one cheapest path.
+//! `all_shortest_paths()` returns a `PathDag` containing
+//! every least-cost path, which can be counted or
+//! enumerated. `k_shortest_paths()` uses [Yen's
+//! algorithm][2] to return the `k` cheapest loopless paths
+//! in order of cost. Both of these require a
+//! [consistent][3] heuristic.
+//!
 //! [1]: http://en.wikipedia.org/wiki/A*_search_algorithm
+//! [2]: http://en.wikipedia.org/wiki/Yen%27s_algorithm
+//! [3]: http://en.wikipedia.org/wiki/Consistent_heuristic
 //!
 //! # Examples
 //!
@@ -78,7 +92,7 @@
 //! ```
 
 use std::cmp::*;
-use std::collections::{BTreeSet, BinaryHeap};
+use std::collections::{BTreeMap, BTreeSet, BinaryHeap};
 
 /// Node with state `S` in A\* search.
 #[derive(Clone, Debug)]
@@ -244,3 +258,439 @@ where
         }
     }
 }
+
+/// Find the least cost of reaching every state within
+/// `max_cost` of the given start state, using supplied
+/// global data. This is a [Dijkstra's Algorithm][1] flood
+/// fill: goals and heuristic costs are ignored.
+///
+/// [1]: https://en.wikipedia.org/wiki/Dijkstra's_algorithm
+pub fn flood_fill<S>(global: &S::Global, start: &S, max_cost: usize) -> BTreeMap<S, usize>
+where
+    S: Clone + Ord + SearchState,
+{
+    let mut dists = BTreeMap::new();
+    let mut pq = BinaryHeap::new();
+    pq.push(PQElem {
+        state: start.clone(),
+        cost: 0,
+        fcost: 0,
+        path: None,
+    });
+    while let Some(PQElem { cost, state, .. }) = pq.pop() {
+        if dists.contains_key(&state) {
+            continue;
+        }
+        for (g_cost, next_state) in state.neighbors(global) {
+            let g = cost + g_cost;
+            if g <= max_cost && !dists.contains_key(&*next_state) {
+                pq.push(PQElem {
+                    fcost: g,
+                    cost: g,
+                    state: *next_state,
+                    path: None,
+                });
+            }
+        }
+        dists.insert(state, cost);
+    }
+    dists
+}
+
+/// The graph of all least-cost paths from a start state to
+/// the goal states, as found by `all_shortest_paths()`.
+/// Every state in the graph lies on some least-cost path.
+#[derive(Clone, Debug)]
+pub struct PathDag<S: SearchState> {
+    /// Cost of the least-cost paths.
+    pub cost: usize,
+    /// Start state of the search.
+    pub start: S,
+    /// Goal states reachable at least cost.
+    pub goals: Vec<S>,
+    /// Cost from the start and least-cost predecessors of
+    /// each state in the graph.
+    nodes: BTreeMap<S, (usize, Vec<S>)>,
+}
+
+impl<S> PathDag<S>
+where
+    S: Clone + Ord + SearchState,
+{
+    /// Return the least cost of reaching the given state
+    /// from the start, if the state is in the graph.
+    pub fn cost_to(&self, state: &S) -> Option<usize> {
+        self.nodes.get(state).map(|&(g, _)| g)
+    }
+
+    /// Return the predecessors of the given state on
+    /// least-cost paths. The start state has none.
+    pub fn predecessors(&self, state: &S) -> &[S] {
+        match self.nodes.get(state) {
+            Some((_, preds)) => preds,
+            None => &[],
+        }
+    }
+
+    /// Return an iterator over the states in the graph.
+    pub fn states(&self) -> impl Iterator<Item = &S> {
+        self.nodes.keys()
+    }
+
+    /// Return the number of distinct least-cost paths.
+    pub fn count_paths(&self) -> u128 {
+        let mut counts = BTreeMap::new();
+        self.goals
+            .iter()
+            .map(|g| self.count_paths_to(g, &mut counts))
+            .sum()
+    }
+
+    /// Memoized count of least-cost paths from the start
+    /// to the given state.
+    fn count_paths_to(&self, state: &S, counts: &mut BTreeMap<S, u128>) -> u128 {
+        if let Some(&n) = counts.get(state) {
+            return n;
+        }
+        let preds = self.predecessors(state);
+        let n = if preds.is_empty() {
+            1
+        } else {
+            preds.iter().map(|p| self.count_paths_to(p, counts)).sum()
+        };
+        counts.insert(state.clone(), n);
+        n
+    }
+
+    /// Return every least-cost path as a sequence of
+    /// labels from start to goal. There may be
+    /// exponentially many of these: check `count_paths()`
+    /// first if in doubt.
+    pub fn paths(&self) -> Vec<Vec<S::Label>> {
+        let mut result = Vec::new();
+        for goal in &self.goals {
+            let mut suffix = vec![goal.label()];
+            self.extend_paths(goal, &mut suffix, &mut result);
+        }
+        result
+    }
+
+    /// Extend the reversed path `suffix` ending at `state`
+    /// backward through every predecessor, saving the
+    /// completed paths.
+    fn extend_paths(&self, state: &S, suffix: &mut Vec<S::Label>, result: &mut Vec<Vec<S::Label>>) {
+        let preds = self.predecessors(state);
+        if preds.is_empty() {
+            result.push(suffix.iter().rev().cloned().collect());
+            return;
+        }
+        for p in preds {
+            suffix.push(p.label());
+            self.extend_paths(p, suffix, result);
+            suffix.pop();
+        }
+    }
+
+    /// Return one least-cost path as a sequence of states
+    /// from start to goal.
+    fn any_path(&self) -> Vec<S> {
+        let mut path = vec![self.goals[0].clone()];
+        loop {
+            let preds = self.predecessors(&path[path.len() - 1]);
+            if preds.is_empty() {
+                break;
+            }
+            path.push(preds[0].clone());
+        }
+        path.reverse();
+        path
+    }
+}
+
+/// A\* search that keeps every least-cost predecessor of
+/// each state, skipping transitions for which `blocked`
+/// returns true. Search continues until no remaining node
+/// could lie on a least-cost path.
+fn optimal_dag<S, F>(global: &S::Global, start: &S, blocked: F) -> Option<PathDag<S>>
+where
+    S: Clone + Ord + SearchState,
+    F: Fn(&S, &S) -> bool,
+{
+    let mut best: BTreeMap<S, (usize, Vec<S>)> = BTreeMap::new();
+    let mut stop_list = BTreeSet::new();
+    let mut goals = Vec::new();
+    let mut optimum = None;
+    let mut pq = BinaryHeap::new();
+    best.insert(start.clone(), (0, Vec::new()));
+    pq.push(PQElem {
+        state: start.clone(),
+        cost: 0,
+        fcost: start.hcost(global),
+        path: None,
+    });
+    while let Some(PQElem {
+        cost, fcost, state, ..
+    }) = pq.pop()
+    {
+        if let Some(opt) = optimum {
+            if fcost > opt {
+                break;
+            }
+        }
+        if cost > best[&state].0 || !stop_list.insert(state.clone()) {
+            continue;
+        }
+        if state.is_goal(global) {
+            optimum = Some(cost);
+            goals.push(state);
+            continue;
+        }
+        for (g_cost, next_state) in state.neighbors(global) {
+            if blocked(&state, &next_state) {
+                continue;
+            }
+            let g = cost + g_cost;
+            match best.get_mut(&*next_state) {
+                Some((g0, preds)) if *g0 == g => {
+                    if !preds.contains(&state) {
+                        preds.push(state.clone());
+                    }
+                    continue;
+                }
+                Some((g0, _)) if *g0 < g => continue,
+                _ => (),
+            }
+            best.insert((*next_state).clone(), (g, vec![state.clone()]));
+            pq.push(PQElem {
+                fcost: g + next_state.hcost(global),
+                cost: g,
+                state: *next_state,
+                path: None,
+            });
+        }
+    }
+    let cost = optimum?;
+
+    // Keep only the states that lie on some least-cost path.
+    let mut nodes = BTreeMap::new();
+    let mut work: Vec<S> = goals.clone();
+    while let Some(state) = work.pop() {
+        if nodes.contains_key(&state) {
+            continue;
+        }
+        let node = best.remove(&state).expect("optimal_dag: lost state");
+        work.extend(node.1.iter().cloned());
+        nodes.insert(state, node);
+    }
+    Some(PathDag {
+        cost,
+        start: start.clone(),
+        goals,
+        nodes,
+    })
+}
+
+/// Find every least-cost path from the given start state to
+/// some goal, using supplied global data. Returns `None` if
+/// no goal is reachable. The heuristic must be
+/// [consistent][1] for the result to be complete.
+///
+/// [1]: http://en.wikipedia.org/wiki/Consistent_heuristic
+pub fn all_shortest_paths<S>(global: &S::Global, start: &S) -> Option<PathDag<S>>
+where
+    S: Clone + Ord + SearchState,
+{
+    optimal_dag(global, start, |_, _| false)
+}
+
+/// A candidate path for `k_shortest_paths()`: cost and
+/// states, with the cost from the start of each state.
+type Route<S> = (usize, Vec<(usize, S)>);
+
+/// Extract a least-cost route from a `PathDag`, offset by
+/// the given cost.
+fn dag_route<S>(dag: &PathDag<S>, offset: usize) -> Route<S>
+where
+    S: Clone + Ord + SearchState,
+{
+    let states = dag
+        .any_path()
+        .into_iter()
+        .map(|s| (offset + dag.cost_to(&s).expect("dag_route: lost state"), s))
+        .collect();
+    (offset + dag.cost, states)
+}
+
+/// Find up to `k` least-cost loopless paths from the given
+/// start state to some goal, using supplied global data, by
+/// [Yen's algorithm][1]. The paths are returned in
+/// nondecreasing order of cost, as costs with label
+/// sequences from start to goal.
+///
+/// Transitions are identified by their source and
+/// destination states, so multiple transitions between the
+/// same pair of states count as one. The heuristic must be
+/// [consistent][2].
+///
+/// [1]: http://en.wikipedia.org/wiki/Yen%27s_algorithm
+/// [2]: http://en.wikipedia.org/wiki/Consistent_heuristic
+pub fn k_shortest_paths<S>(global: &S::Global, start: &S, k: usize) -> Vec<(usize, Vec<S::Label>)>
+where
+    S: Clone + Ord + SearchState,
+{
+    let mut found: Vec<Route<S>> = Vec::new();
+    if k == 0 {
+        return Vec::new();
+    }
+    match optimal_dag(global, start, |_, _| false) {
+        Some(dag) => found.push(dag_route(&dag, 0)),
+        None => return Vec::new(),
+    }
+    let mut candidates: Vec<Route<S>> = Vec::new();
+    while found.len() < k {
+        let last = &found[found.len() - 1].1;
+        for i in 0..last.len() - 1 {
+            let (spur_cost, ref spur) = last[i];
+            let root = &last[..=i];
+
+            // Block transitions out of the spur state taken
+            // by earlier paths that share this root.
+            let mut used = BTreeSet::new();
+            for (_, path) in &found {
+                if path.len() > i + 1
+                    && path[..=i]
+                        .iter()
+                        .map(|(_, s)| s)
+                        .eq(root.iter().map(|(_, s)| s))
+                {
+                    used.insert(path[i + 1].1.clone());
+                }
+            }
+
+            // Block revisiting the rest of the root.
+            let visited: BTreeSet<&S> = last[..i].iter().map(|(_, s)| s).collect();
+            let blocked =
+                |from: &S, to: &S| visited.contains(to) || (from == spur && used.contains(to));
+            let dag = match optimal_dag(global, spur, blocked) {
+                Some(dag) => dag,
+                None => continue,
+            };
+            let (cost, spur_path) = dag_route(&dag, spur_cost);
+            let mut path = last[..i].to_vec();
+            path.extend(spur_path);
+            let route = (cost, path);
+            if !candidates.contains(&route) && !found.contains(&route) {
+                candidates.push(route);
+            }
+        }
+        let next = match candidates.iter().enumerate().min_by_key(|(_, (c, _))| *c) {
+            Some((j, _)) => j,
+            None => break,
+        };
+        found.push(candidates.remove(next));
+    }
+    found
+        .into_iter()
+        .map(|(cost, path)| (cost, path.iter().map(|(_, s)| s.label()).collect()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    /// Walk on an open grid from `(0, 0)` to a goal corner,
+    /// moving right or down at unit cost or diagonally
+    /// down-right at cost 3.
+    #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+    struct Walk((usize, usize));
+
+    impl SearchState for Walk {
+        type Label = (usize, usize);
+        type Global = (usize, usize);
+
+        fn label(&self) -> (usize, usize) {
+            self.0
+        }
+
+        fn neighbors(&self, goal: &(usize, usize)) -> Vec<(usize, Box<Walk>)> {
+            let (x, y) = self.0;
+            let mut result = Vec::new();
+            if x < goal.0 {
+                result.push((1, Box::new(Walk((x + 1, y)))));
+            }
+            if y < goal.1 {
+                result.push((1, Box::new(Walk((x, y + 1)))));
+            }
+            if x < goal.0 && y < goal.1 {
+                result.push((3, Box::new(Walk((x + 1, y + 1)))));
+            }
+            result
+        }
+
+        fn is_goal(&self, goal: &(usize, usize)) -> bool {
+            self.0 == *goal
+        }
+
+        fn hcost(&self, goal: &(usize, usize)) -> usize {
+            goal.0.saturating_sub(self.0 .0) + goal.1.saturating_sub(self.0 .1)
+        }
+    }
+
+    #[test]
+    fn dag_counts_lattice_paths() {
+        let dag = all_shortest_paths(&(3, 2), &Walk((0, 0))).expect("no dag");
+        assert_eq!(dag.cost, 5);
+        assert_eq!(dag.count_paths(), 10);
+        let paths = dag.paths();
+        assert_eq!(paths.len(), 10);
+        for p in &paths {
+            assert_eq!(p.len(), 6);
+            assert_eq!(p[0], (0, 0));
+            assert_eq!(p[5], (3, 2));
+        }
+        assert_eq!(dag.predecessors(&Walk((0, 0))), &[]);
+        assert_eq!(dag.cost_to(&Walk((2, 1))), Some(3));
+    }
+
+    #[test]
+    fn flood_fill_triangle() {
+        let dists = flood_fill(&(5, 5), &Walk((0, 0)), 2);
+        assert_eq!(dists.len(), 6);
+        assert_eq!(dists[&Walk((1, 1))], 2);
+        assert!(!dists.contains_key(&Walk((3, 0))));
+    }
+
+    #[test]
+    fn dag_unreachable() {
+        assert!(all_shortest_paths(&(1, 1), &Walk((2, 2))).is_none());
+    }
+
+    #[test]
+    fn yen_orders_paths() {
+        let paths = k_shortest_paths(&(1, 1), &Walk((0, 0)), 5);
+        let costs: Vec<usize> = paths.iter().map(|(c, _)| *c).collect();
+        assert_eq!(costs, vec![2, 2, 3]);
+        assert_eq!(paths[2].1, vec![(0, 0), (1, 1)]);
+        assert_ne!(paths[0].1, paths[1].1);
+    }
+
+    #[test]
+    fn yen_agrees_with_dag() {
+        let goal = (3, 3);
+        let dag = all_shortest_paths(&goal, &Walk((0, 0))).expect("no dag");
+        let n = dag.count_paths() as usize;
+        let paths = k_shortest_paths(&goal, &Walk((0, 0)), n + 1);
+        assert_eq!(paths.len(), n + 1);
+        for (cost, _) in &paths[..n] {
+            assert_eq!(*cost, dag.cost);
+        }
+        assert_eq!(paths[n].0, dag.cost + 1);
+        let mut yen: Vec<_> = paths[..n].iter().map(|(_, p)| p.clone()).collect();
+        let mut all = dag.paths();
+        yen.sort();
+        all.sort();
+        assert_eq!(yen, all);
+    }
+}
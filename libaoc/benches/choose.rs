@@ -0,0 +1,94 @@
+// Copyright © 2016 Bart Massey
+// This program is licensed under the "MIT License".
+// Please see the file COPYING in this distribution
+// for license terms.
+
+//! Benchmark of eager versus lazy subset choice, sized
+//! like the Day 11 neighbor expansion: all grabs of at most
+//! two devices from a floor, alone and with the new source
+//! and destination floors built for each grab.
+
+use std::collections::BTreeSet;
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+use aoc::Set;
+
+/// A floor holding a generator and chip for each of five
+/// materials, as at the start of Day 11 part 1.
+fn floor() -> BTreeSet<String> {
+    let materials = ["cobalt", "curium", "plutonium", "promethium", "ruthenium"];
+    let mut floor = BTreeSet::new();
+    for m in materials {
+        floor.insert(format!("{} generator", m));
+        floor.insert(format!("{}-compatible microchip", m));
+    }
+    floor
+}
+
+fn bench_choose(c: &mut Criterion) {
+    let floor = floor();
+    c.bench_function("choose_le", |b| {
+        b.iter(|| {
+            aoc::choose_le(black_box(&floor), 2)
+                .into_iter()
+                .map(|grab| grab.len())
+                .sum::<usize>()
+        })
+    });
+    c.bench_function("set_combinations_le", |b| {
+        b.iter(|| {
+            aoc::set_combinations_le(black_box(&floor), 2)
+                .map(|grab| grab.len())
+                .sum::<usize>()
+        })
+    });
+}
+
+/// Day 11 neighbor expansion from `floor` to an empty
+/// floor, old style: eager grabs, each a set, moved by set
+/// difference and union.
+fn expand_eager(floor: &BTreeSet<String>) -> usize {
+    let dest = BTreeSet::new();
+    aoc::choose_le(floor, 2)
+        .into_iter()
+        .map(|grab| {
+            let new_src = Set::difference(floor, &grab);
+            let new_dest = Set::union(&dest, &grab);
+            new_src.len() + new_dest.len()
+        })
+        .sum()
+}
+
+/// Day 11 neighbor expansion from `floor` to an empty
+/// floor, new style: lazy grabs of references, moved
+/// element by element.
+fn expand_lazy(floor: &BTreeSet<String>) -> usize {
+    let dest = BTreeSet::new();
+    let here: Vec<&String> = floor.iter().collect();
+    aoc::combinations_le(&here, 2)
+        .map(|grab| {
+            let mut new_src = floor.clone();
+            let mut new_dest = dest.clone();
+            for &dev in &grab {
+                new_src.remove(dev);
+                new_dest.insert(dev.clone());
+            }
+            new_src.len() + new_dest.len()
+        })
+        .sum()
+}
+
+fn bench_expand(c: &mut Criterion) {
+    let floor = floor();
+    assert_eq!(expand_eager(&floor), expand_lazy(&floor));
+    c.bench_function("expand_choose_le", |b| {
+        b.iter(|| expand_eager(black_box(&floor)))
+    });
+    c.bench_function("expand_combinations_le", |b| {
+        b.iter(|| expand_lazy(black_box(&floor)))
+    });
+}
+
+criterion_group!(benches, bench_choose, bench_expand);
+criterion_main!(benches);
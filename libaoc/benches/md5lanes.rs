@@ -0,0 +1,73 @@
+// Copyright © 2016 Bart Massey
+// This program is licensed under the "MIT License".
+// Please see the file COPYING in this distribution
+// for license terms.
+
+//! Benchmark of multi-lane MD5 against the `md-5` crate,
+//! hashing a salt followed by decimal indices as in the
+//! Day 5 and Day 14 searches.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+
+use aoc::{Md5, PuzzleHasher};
+
+/// Salt for the messages.
+const SALT: &[u8] = b"ngcjuoqr";
+
+/// Number of messages hashed per iteration.
+const BATCH: usize = 64;
+
+/// The messages for a batch starting at index `start`.
+fn messages(start: usize) -> Vec<Vec<u8>> {
+    (start..start + BATCH)
+        .map(|i| {
+            let mut message = SALT.to_vec();
+            message.extend_from_slice(i.to_string().as_bytes());
+            message
+        })
+        .collect()
+}
+
+fn bench_md5lanes(c: &mut Criterion) {
+    let messages = messages(1_000_000);
+    let mut group = c.benchmark_group("md5");
+    group.throughput(Throughput::Elements(BATCH as u64));
+    group.bench_function("md-5", |b| {
+        b.iter(|| {
+            black_box(&messages)
+                .iter()
+                .map(|m| Md5::digest(m)[0])
+                .fold(0, |acc, x| acc ^ x)
+        })
+    });
+    group.bench_function("md5x4", |b| {
+        b.iter(|| {
+            black_box(&messages)
+                .chunks_exact(4)
+                .map(|m| aoc::md5x4([&m[0], &m[1], &m[2], &m[3]])[0][0])
+                .fold(0, |acc, x| acc ^ x)
+        })
+    });
+    group.bench_function("md5x8", |b| {
+        b.iter(|| {
+            black_box(&messages)
+                .chunks_exact(8)
+                .map(|m| {
+                    let refs: [&[u8]; 8] = std::array::from_fn(|l| m[l].as_slice());
+                    aoc::md5x8(refs)[0][0]
+                })
+                .fold(0, |acc, x| acc ^ x)
+        })
+    });
+    group.bench_function("md5_indices", |b| {
+        b.iter(|| {
+            (0..BATCH / 8)
+                .map(|k| aoc::md5_indices::<8>(black_box(SALT), 1_000_000 + 8 * k)[0][0])
+                .fold(0, |acc, x| acc ^ x)
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_md5lanes);
+criterion_main!(benches);
@@ -0,0 +1,497 @@
+// Copyright © 2016 Bart Massey
+// This program is licensed under the "MIT License".
+// Please see the file COPYING in this distribution
+// for license terms.
+
+//! Packed rows of cells for elementary cellular automata.
+//!
+//! A `BitRow` is a fixed-length row of boolean cells stored
+//! 64 to a word. `BitRow::step()` applies any elementary
+//! (Wolfram-numbered) rule to every cell of the row a word
+//! at a time: bit `4*l + 2*c + r` of the rule number gives
+//! the next state of a cell with left neighbor `l`, state `c`
+//! and right neighbor `r`. Cells off the ends of the row are
+//! supplied by a `Boundary`.
+//!
+//! A `BitRow` also serves as a general bit vector, with
+//! operations such as `reverse_complement()`,
+//! `dragon_step()` and `pairwise_xnor_reduce()`. Rows parse
+//! from and display as `1`/`0` text; `parse_with()` and
+//! `render()` use other characters.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use aoc::{BitRow, Boundary};
+//!
+//! // Rule 90: each cell becomes the xor of its neighbors.
+//! let mut row: BitRow = "0001000".chars().map(|c| c == '1').collect();
+//! row = row.step(90, Boundary::Fixed(false));
+//! assert_eq!(row.to_string(), "0010100");
+//! row = row.step(90, Boundary::Fixed(false));
+//! assert_eq!(row.to_string(), "0100010");
+//! assert_eq!(row.count_ones(), 2);
+//!
+//! let data: BitRow = "10000".parse().unwrap();
+//! assert_eq!(data.dragon_step().to_string(), "10000011110");
+//! assert_eq!(data.reverse_complement().render('#', '.'), "####.");
+//! let data: BitRow = "110010110100".parse().unwrap();
+//! assert_eq!(data.pairwise_xnor_reduce().to_string(), "110101");
+//! assert!("10x".parse::<BitRow>().is_err());
+//! ```
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::bits::popcount;
+
+/// Bits per word.
+const WORD: usize = 64;
+
+/// Value of the cells beyond the ends of a row.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Boundary {
+    /// Cells beyond the ends always have the given state.
+    Fixed(bool),
+    /// The row wraps around: the left end is next to the
+    /// right end.
+    Periodic,
+}
+
+/// Fixed-length row of boolean cells, packed into words.
+/// Cell `i` is bit `i % 64` of word `i / 64`; bits past the
+/// end of the row are always zero.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct BitRow {
+    /// Number of cells.
+    len: usize,
+    /// Packed cells.
+    words: Vec<u64>,
+}
+
+impl BitRow {
+    /// A row of `len` cells, all `false`.
+    pub fn new(len: usize) -> Self {
+        BitRow {
+            len,
+            words: vec![0; len.div_ceil(WORD)],
+        }
+    }
+
+    /// Number of cells in the row.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// True if the row has no cells.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// State of cell `i`.
+    pub fn get(&self, i: usize) -> bool {
+        assert!(i < self.len, "BitRow index out of range");
+        (self.words[i / WORD] >> (i % WORD)) & 1 == 1
+    }
+
+    /// Set the state of cell `i`.
+    pub fn set(&mut self, i: usize, value: bool) {
+        assert!(i < self.len, "BitRow index out of range");
+        let mask = 1 << (i % WORD);
+        if value {
+            self.words[i / WORD] |= mask;
+        } else {
+            self.words[i / WORD] &= !mask;
+        }
+    }
+
+    /// Number of `true` cells in the row.
+    pub fn count_ones(&self) -> usize {
+        self.words.iter().map(|&w| popcount(w)).sum()
+    }
+
+    /// Number of `false` cells in the row.
+    pub fn count_zeros(&self) -> usize {
+        self.len - self.count_ones()
+    }
+
+    /// Iterator over the cells of the row, left to right.
+    pub fn iter(&self) -> impl Iterator<Item = bool> + '_ {
+        (0..self.len).map(move |i| self.get(i))
+    }
+
+    /// Mask of the valid bits of the last word.
+    fn last_mask(&self) -> u64 {
+        match self.len % WORD {
+            0 => !0,
+            n => (1 << n) - 1,
+        }
+    }
+
+    /// Clear the bits past the end of the row.
+    fn trim(&mut self) {
+        let mask = self.last_mask();
+        if let Some(last) = self.words.last_mut() {
+            *last &= mask;
+        }
+    }
+
+    /// Add a cell to the right end of the row.
+    pub fn push(&mut self, value: bool) {
+        if self.len.is_multiple_of(WORD) {
+            self.words.push(0);
+        }
+        self.len += 1;
+        self.set(self.len - 1, value);
+    }
+
+    /// Add the cells of `other` to the right end of the row.
+    pub fn append(&mut self, other: &BitRow) {
+        let off = self.len % WORD;
+        if off == 0 {
+            self.words.extend_from_slice(&other.words);
+        } else {
+            for &w in &other.words {
+                let last = self.words.len() - 1;
+                self.words[last] |= w << off;
+                self.words.push(w >> (WORD - off));
+            }
+        }
+        self.len += other.len;
+        self.words.truncate(self.len.div_ceil(WORD));
+    }
+
+    /// Shorten the row to `len` cells. Has no effect if the
+    /// row is already no longer than that.
+    pub fn truncate(&mut self, len: usize) {
+        if len < self.len {
+            self.len = len;
+            self.words.truncate(len.div_ceil(WORD));
+            self.trim();
+        }
+    }
+
+    /// The row reversed end-for-end with every cell
+    /// complemented.
+    pub fn reverse_complement(&self) -> BitRow {
+        // Reversing the words and their bits puts cell `i`
+        // at position `nwords * 64 - 1 - i`: shift down by
+        // the padding to line things back up.
+        let nwords = self.words.len();
+        let pad = nwords * WORD - self.len;
+        let rev: Vec<u64> = self.words.iter().rev().map(|w| !w.reverse_bits()).collect();
+        let words = (0..nwords)
+            .map(|i| match rev.get(i + 1) {
+                Some(&next) if pad > 0 => (rev[i] >> pad) | (next << (WORD - pad)),
+                _ => rev[i] >> pad,
+            })
+            .collect();
+        let mut result = BitRow {
+            len: self.len,
+            words,
+        };
+        result.trim();
+        result
+    }
+
+    /// One step of the "dragon curve": the row, then a
+    /// `false` cell, then the reverse complement of the row.
+    pub fn dragon_step(&self) -> BitRow {
+        let mut result = self.clone();
+        result.push(false);
+        result.append(&self.reverse_complement());
+        result
+    }
+
+    /// Replace each pair of adjacent cells with a single
+    /// cell that is `true` exactly when the pair agrees. The
+    /// row must have even length.
+    pub fn pairwise_xnor_reduce(&self) -> BitRow {
+        assert!(self.len.is_multiple_of(2), "odd-length BitRow");
+        // Pairs never straddle words, so each word reduces
+        // to 32 bits in its even positions.
+        let reduce = |w: u64| even_bits(!(w ^ (w >> 1)));
+        let words = self
+            .words
+            .chunks(2)
+            .map(|pair| reduce(pair[0]) | pair.get(1).map_or(0, |&w| reduce(w) << 32))
+            .collect();
+        let mut result = BitRow {
+            len: self.len / 2,
+            words,
+        };
+        result.trim();
+        result
+    }
+
+    /// Parse a row from text, with `one` standing for a
+    /// `true` cell and `zero` for a `false` cell.
+    pub fn parse_with(s: &str, one: char, zero: char) -> Result<BitRow, ParseBitRowError> {
+        s.chars()
+            .enumerate()
+            .map(|(posn, c)| match c {
+                c if c == one => Ok(true),
+                c if c == zero => Ok(false),
+                found => Err(ParseBitRowError { posn, found }),
+            })
+            .collect()
+    }
+
+    /// Show the row as text, with `one` standing for a
+    /// `true` cell and `zero` for a `false` cell.
+    pub fn render(&self, one: char, zero: char) -> String {
+        self.iter().map(|b| if b { one } else { zero }).collect()
+    }
+
+    /// Cell `i` of the row, or the boundary value if `i` is
+    /// off either end. Used for the word carries in `step_into()`.
+    fn cell_or(&self, i: isize, boundary: Boundary) -> u64 {
+        let n = self.len as isize;
+        let i = match boundary {
+            Boundary::Fixed(b) if i < 0 || i >= n => return b as u64,
+            Boundary::Fixed(_) => i,
+            Boundary::Periodic => i.rem_euclid(n),
+        };
+        self.get(i as usize) as u64
+    }
+
+    /// Apply elementary CA `rule` to every cell of the row,
+    /// writing the result into `next`, which is resized as
+    /// needed. This avoids allocating a new row per
+    /// generation.
+    pub fn step_into(&self, rule: u8, boundary: Boundary, next: &mut BitRow) {
+        next.len = self.len;
+        next.words.resize(self.words.len(), 0);
+        if self.len == 0 {
+            return;
+        }
+        let nwords = self.words.len();
+        let tail = (self.len - 1) % WORD;
+        for (w, out) in next.words.iter_mut().enumerate() {
+            let cur = self.words[w];
+            let base = (w * WORD) as isize;
+            // Left neighbors: shift up, carrying in the cell
+            // just before this word.
+            let left = (cur << 1) | self.cell_or(base - 1, boundary);
+            // Right neighbors: shift down, carrying in the cell
+            // just after this word's last cell.
+            let right = if w + 1 < nwords {
+                (cur >> 1) | (self.words[w + 1] << (WORD - 1))
+            } else {
+                let after = self.cell_or(self.len as isize, boundary);
+                (cur >> 1) | (after << tail)
+            };
+            let mut result = 0;
+            for pat in 0..8 {
+                if rule & (1 << pat) == 0 {
+                    continue;
+                }
+                let l = if pat & 4 != 0 { left } else { !left };
+                let c = if pat & 2 != 0 { cur } else { !cur };
+                let r = if pat & 1 != 0 { right } else { !right };
+                result |= l & c & r;
+            }
+            *out = result;
+        }
+        next.trim();
+    }
+
+    /// Apply elementary CA `rule` to every cell of the row,
+    /// returning the next generation.
+    pub fn step(&self, rule: u8, boundary: Boundary) -> BitRow {
+        let mut next = BitRow::new(self.len);
+        self.step_into(rule, boundary, &mut next);
+        next
+    }
+}
+
+/// Gather the even-position bits of `x` into its low 32
+/// bits.
+fn even_bits(x: u64) -> u64 {
+    let mut x = x & 0x5555_5555_5555_5555;
+    x = (x | (x >> 1)) & 0x3333_3333_3333_3333;
+    x = (x | (x >> 2)) & 0x0f0f_0f0f_0f0f_0f0f;
+    x = (x | (x >> 4)) & 0x00ff_00ff_00ff_00ff;
+    x = (x | (x >> 8)) & 0x0000_ffff_0000_ffff;
+    (x | (x >> 16)) & 0x0000_0000_ffff_ffff
+}
+
+impl FromIterator<bool> for BitRow {
+    fn from_iter<I: IntoIterator<Item = bool>>(iter: I) -> Self {
+        let mut row = BitRow::new(0);
+        for b in iter {
+            row.push(b);
+        }
+        row
+    }
+}
+
+/// Error from parsing a `BitRow` from text.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseBitRowError {
+    /// Character position of the bad character.
+    pub posn: usize,
+    /// The bad character.
+    pub found: char,
+}
+
+impl fmt::Display for ParseBitRowError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "unexpected character {:?} at position {}",
+            self.found, self.posn
+        )
+    }
+}
+
+impl std::error::Error for ParseBitRowError {}
+
+/// Rows are parsed from `1` and `0`, left to right.
+impl FromStr for BitRow {
+    type Err = ParseBitRowError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        BitRow::parse_with(s, '1', '0')
+    }
+}
+
+/// Cells are shown as `1` and `0`, left to right.
+impl fmt::Display for BitRow {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.render('1', '0'))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand::prelude::*;
+
+    /// Cell-at-a-time reference for `BitRow::step()`.
+    fn step_naive(row: &[bool], rule: u8, boundary: Boundary) -> Vec<bool> {
+        let n = row.len() as isize;
+        let cell = |i: isize| match boundary {
+            Boundary::Fixed(b) if i < 0 || i >= n => b,
+            Boundary::Fixed(_) => row[i as usize],
+            Boundary::Periodic => row[i.rem_euclid(n) as usize],
+        };
+        (0..n)
+            .map(|i| {
+                let pat = 4 * cell(i - 1) as u8 + 2 * cell(i) as u8 + cell(i + 1) as u8;
+                rule & (1 << pat) != 0
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_step_random() {
+        let mut seed = [0; 32];
+        seed[0..4].copy_from_slice(&[0xca, 0xca, 0xca, 0xca]);
+        let mut rng = StdRng::from_seed(seed);
+        let boundaries = [
+            Boundary::Fixed(false),
+            Boundary::Fixed(true),
+            Boundary::Periodic,
+        ];
+        for _ in 0..300 {
+            let len = rng.gen_range(1..200);
+            let rule = rng.gen();
+            let boundary = boundaries[rng.gen_range(0..3)];
+            let cells: Vec<bool> = (0..len).map(|_| rng.gen()).collect();
+            let row: BitRow = cells.iter().cloned().collect();
+            let next = row.step(rule, boundary);
+            let expected = step_naive(&cells, rule, boundary);
+            assert_eq!(next.iter().collect::<Vec<_>>(), expected, "rule {}", rule);
+            assert_eq!(next.count_ones(), expected.iter().filter(|&&b| b).count());
+        }
+    }
+
+    #[test]
+    fn test_bit_vector_ops() {
+        let mut seed = [0; 32];
+        seed[0..4].copy_from_slice(&[0xd7, 0xa6, 0x07, 0x16]);
+        let mut rng = StdRng::from_seed(seed);
+        for _ in 0..300 {
+            let len = rng.gen_range(0..300);
+            let cells: Vec<bool> = (0..len).map(|_| rng.gen()).collect();
+            let row: BitRow = cells.iter().cloned().collect();
+
+            let rc: Vec<bool> = cells.iter().rev().map(|&b| !b).collect();
+            assert_eq!(row.reverse_complement().iter().collect::<Vec<_>>(), rc);
+
+            let mut dragon = cells.clone();
+            dragon.push(false);
+            dragon.extend(rc.iter().cloned());
+            let dragon_row = row.dragon_step();
+            assert_eq!(dragon_row.iter().collect::<Vec<_>>(), dragon);
+            assert_eq!(dragon_row.count_ones(), len);
+
+            let even = &cells[..len & !1];
+            let xnor: Vec<bool> = even.chunks(2).map(|p| p[0] == p[1]).collect();
+            let mut even_row = row.clone();
+            even_row.truncate(even.len());
+            assert_eq!(even_row.count_ones(), even.iter().filter(|&&b| b).count());
+            let reduced = even_row.pairwise_xnor_reduce();
+            assert_eq!(reduced.iter().collect::<Vec<_>>(), xnor);
+            assert_eq!(reduced.count_ones(), xnor.iter().filter(|&&b| b).count());
+
+            let text = row.to_string();
+            assert_eq!(text.parse::<BitRow>(), Ok(row));
+        }
+    }
+
+    #[test]
+    fn test_dragon_examples() {
+        let tests = [
+            ("1", "100"),
+            ("0", "001"),
+            ("11111", "11111000000"),
+            ("111100001010", "1111000010100101011110000"),
+        ];
+        for (src, dst) in tests {
+            let src: BitRow = src.parse().unwrap();
+            assert_eq!(src.dragon_step().to_string(), dst);
+        }
+        let data: BitRow = "110010110100".parse().unwrap();
+        let reduced = data.pairwise_xnor_reduce();
+        assert_eq!(reduced.to_string(), "110101");
+        assert_eq!(reduced.pairwise_xnor_reduce().to_string(), "100");
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            "0110x1".parse::<BitRow>(),
+            Err(ParseBitRowError {
+                posn: 4,
+                found: 'x'
+            })
+        );
+        let err = BitRow::parse_with(".^.1", '.', '^').unwrap_err();
+        assert_eq!(err.to_string(), "unexpected character '1' at position 3");
+        assert_eq!("".parse::<BitRow>(), Ok(BitRow::new(0)));
+    }
+
+    #[test]
+    fn test_rule_30() {
+        let mut row: BitRow = "0000010000".chars().map(|c| c == '1').collect();
+        let mut rows = Vec::new();
+        for _ in 0..4 {
+            rows.push(row.to_string());
+            row = row.step(30, Boundary::Fixed(false));
+        }
+        assert_eq!(
+            rows,
+            ["0000010000", "0000111000", "0001100100", "0011011110"]
+        );
+    }
+
+    #[test]
+    fn test_periodic_wraps() {
+        let row: BitRow = [true, false, false, false].iter().cloned().collect();
+        let next = row.step(90, Boundary::Periodic);
+        assert_eq!(next.to_string(), "0101");
+        assert!(BitRow::new(0).step(255, Boundary::Periodic).is_empty());
+    }
+}
@@ -4,23 +4,31 @@
 // for license terms.
 
 //! Bit operations for Advent of Code 2016 solutions.
+//!
+//! The operations here work on any unsigned integer type,
+//! via the `Bits` trait. Some of them duplicate `std`
+//! inherent methods, but are provided so that generic code
+//! can use them.
+//!
+//! # Examples
+//!
+//! ```rust
+//! assert_eq!(aoc::popcount(0xf0u8), 4);
+//! assert!(aoc::parity(0x7u16));
+//! assert_eq!(aoc::reverse_bits(0x1u32), 0x8000_0000);
+//! assert_eq!(aoc::gray_decode(aoc::gray_code(77u64)), 77);
+//! assert_eq!(aoc::next_bit_permutation(0b0111u8), Some(0b1011));
+//! assert_eq!(aoc::extract_bits(0xabcdu16, 4, 8), 0xbc);
+//! ```
 
-/// This popcount uses ivide-and-conquer with a quaternary
+/// This popcount uses divide-and-conquer with a quaternary
 /// stage to reduce masking and provide mostly power-of-two
 /// shifts. See <http://github.com/BartMassey/popcount> for
 /// details.  This is "popcount_4" there, modified for
 /// 64-bit.  This code will be slower than it needs to be on
 /// narrower inputs.
-///
-/// # Examples
-///
-/// ```rust
-/// assert_eq!(aoc::popcount(0x5555u64 << 32), 8);
-/// ```
-
 #[inline]
-pub fn popcount<T: Into<u64>>(x0: T) -> usize {
-    let mut x: u64 = x0.into();
+fn popcount_u64(mut x: u64) -> usize {
     let m1 = 0x5555555555555555;
     let m2 = 0x0303030303030303;
     x -= (x >> 1) & m1;
@@ -30,13 +38,221 @@ pub fn popcount<T: Into<u64>>(x0: T) -> usize {
     ((x + (x >> 32)) & 0xff) as usize
 }
 
+/// Unsigned integer types supporting the operations of this
+/// module. Prefer the free functions, since several of
+/// these methods are shadowed by inherent methods of the
+/// same name.
+pub trait Bits: Copy + Eq {
+    /// Width of the type in bits.
+    const WIDTH: usize;
+
+    /// Number of one bits.
+    fn popcount(self) -> usize;
+
+    /// True iff the number of one bits is odd.
+    fn parity(self) -> bool;
+
+    /// Number of zero bits below the lowest one bit, or
+    /// `WIDTH` for zero.
+    fn trailing_zeros(self) -> usize;
+
+    /// Number of zero bits above the highest one bit, or
+    /// `WIDTH` for zero.
+    fn leading_zeros(self) -> usize;
+
+    /// Bits in reverse order.
+    fn reverse_bits(self) -> Self;
+
+    /// Binary-reflected Gray code.
+    fn gray_code(self) -> Self;
+
+    /// Inverse of `gray_code()`.
+    fn gray_decode(self) -> Self;
+
+    /// Next larger value with the same popcount, if any.
+    fn next_bit_permutation(self) -> Option<Self>;
+
+    /// The `len`-bit field starting at bit `lo`.
+    fn extract_bits(self, lo: usize, len: usize) -> Self;
+}
+
+/// Implement the parts of `Bits` that do not depend on
+/// width.
+macro_rules! bits_common {
+    ($t:ty) => {
+        const WIDTH: usize = <$t>::BITS as usize;
+
+        #[inline]
+        fn parity(self) -> bool {
+            let mut x = self;
+            let mut shift = Self::WIDTH / 2;
+            while shift > 0 {
+                x ^= x >> shift;
+                shift /= 2;
+            }
+            x & 1 == 1
+        }
+
+        #[inline]
+        fn trailing_zeros(self) -> usize {
+            <$t>::trailing_zeros(self) as usize
+        }
+
+        #[inline]
+        fn leading_zeros(self) -> usize {
+            <$t>::leading_zeros(self) as usize
+        }
+
+        #[inline]
+        fn reverse_bits(self) -> $t {
+            <$t>::reverse_bits(self)
+        }
+
+        #[inline]
+        fn gray_code(self) -> $t {
+            self ^ (self >> 1)
+        }
+
+        #[inline]
+        fn gray_decode(self) -> $t {
+            let mut x = self;
+            let mut shift = 1;
+            while shift < Self::WIDTH {
+                x ^= x >> shift;
+                shift *= 2;
+            }
+            x
+        }
+
+        /// This is "Gosper's Hack": see
+        /// <http://graphics.stanford.edu/~seander/bithacks.html#NextBitPermutation>.
+        #[inline]
+        fn next_bit_permutation(self) -> Option<$t> {
+            if self == 0 {
+                return None;
+            }
+            let t = self | (self - 1);
+            let next = t.checked_add(1)?;
+            let low = ((next & next.wrapping_neg()) - 1)
+                .checked_shr(<$t>::trailing_zeros(self) + 1)
+                .unwrap_or(0);
+            Some(next | low)
+        }
+
+        #[inline]
+        fn extract_bits(self, lo: usize, len: usize) -> $t {
+            let x = self.checked_shr(lo as u32).unwrap_or(0);
+            if len >= Self::WIDTH {
+                x
+            } else {
+                x & ((1 << len) - 1)
+            }
+        }
+    };
+}
+
+/// Implement `Bits` for types no wider than 64 bits.
+macro_rules! bits_narrow {
+    ($($t:ty),*) => {$(
+        impl Bits for $t {
+            bits_common!($t);
+
+            #[inline]
+            fn popcount(self) -> usize {
+                popcount_u64(self as u64)
+            }
+        }
+    )*};
+}
+
+bits_narrow!(u8, u16, u32, u64, usize);
+
+impl Bits for u128 {
+    bits_common!(u128);
+
+    #[inline]
+    fn popcount(self) -> usize {
+        popcount_u64(self as u64) + popcount_u64((self >> 64) as u64)
+    }
+}
+
+/// Number of one bits.
+///
+/// # Examples
+///
+/// ```rust
+/// assert_eq!(aoc::popcount(0x5555u64 << 32), 8);
+/// ```
+#[inline]
+pub fn popcount<T: Bits>(x: T) -> usize {
+    x.popcount()
+}
+
+/// True iff the number of one bits is odd.
+#[inline]
+pub fn parity<T: Bits>(x: T) -> bool {
+    x.parity()
+}
+
+/// Number of zero bits below the lowest one bit, or the
+/// type width for zero.
+#[inline]
+pub fn trailing_zeros<T: Bits>(x: T) -> usize {
+    Bits::trailing_zeros(x)
+}
+
+/// Number of zero bits above the highest one bit, or the
+/// type width for zero.
+#[inline]
+pub fn leading_zeros<T: Bits>(x: T) -> usize {
+    Bits::leading_zeros(x)
+}
+
+/// Bits in reverse order.
+#[inline]
+pub fn reverse_bits<T: Bits>(x: T) -> T {
+    Bits::reverse_bits(x)
+}
+
+/// [Binary-reflected Gray code][1]: successive values
+/// differ in exactly one bit.
+///
+/// [1]: http://en.wikipedia.org/wiki/Gray_code
+#[inline]
+pub fn gray_code<T: Bits>(x: T) -> T {
+    x.gray_code()
+}
+
+/// Inverse of `gray_code()`.
+#[inline]
+pub fn gray_decode<T: Bits>(x: T) -> T {
+    x.gray_decode()
+}
+
+/// Next larger value with the same number of one bits, or
+/// `None` if there is none in the type. Zero has no
+/// successor. Starting from `(1 << k) - 1`, this steps
+/// through the `k`-subsets of the bit positions in
+/// increasing order.
+#[inline]
+pub fn next_bit_permutation<T: Bits>(x: T) -> Option<T> {
+    x.next_bit_permutation()
+}
+
+/// The `len`-bit field of `x` starting at bit `lo`, shifted
+/// down to bit 0. Bits beyond the type width read as zero.
+#[inline]
+pub fn extract_bits<T: Bits>(x: T, lo: usize, len: usize) -> T {
+    x.extract_bits(lo, len)
+}
+
 #[cfg(test)]
 mod test {
 
     extern crate rand;
 
     use self::rand::prelude::*;
-    use super::popcount;
+    use super::*;
 
     /// The obvious naive implementation of popcount.
     fn popcount_naive(mut x: u64) -> usize {
@@ -73,4 +289,140 @@ mod test {
             };
         }
     }
+
+    /// Naive bit `i` of `x`, for any width.
+    fn bit(x: u128, i: usize) -> u128 {
+        x.checked_shr(i as u32).unwrap_or(0) & 1
+    }
+
+    /// Naive references for the `Bits` operations, on
+    /// values of the given width held in a `u128`.
+    fn parity_naive(x: u128, width: usize) -> bool {
+        (0..width).filter(|&i| bit(x, i) == 1).count() % 2 == 1
+    }
+
+    fn trailing_zeros_naive(x: u128, width: usize) -> usize {
+        (0..width).take_while(|&i| bit(x, i) == 0).count()
+    }
+
+    fn leading_zeros_naive(x: u128, width: usize) -> usize {
+        (0..width).rev().take_while(|&i| bit(x, i) == 0).count()
+    }
+
+    fn reverse_bits_naive(x: u128, width: usize) -> u128 {
+        (0..width).map(|i| bit(x, i) << (width - 1 - i)).sum()
+    }
+
+    fn gray_code_naive(x: u128, width: usize) -> u128 {
+        (0..width).map(|i| (bit(x, i) ^ bit(x, i + 1)) << i).sum()
+    }
+
+    fn gray_decode_naive(x: u128, width: usize) -> u128 {
+        (0..width)
+            .map(|i| ((i..width).map(|j| bit(x, j)).sum::<u128>() & 1) << i)
+            .sum()
+    }
+
+    /// The next larger value with `k` ones must differ from
+    /// `x` first (from the top) at some zero bit `p` of `x`
+    /// that it sets. Keeping the bits above `p` and packing
+    /// the remaining ones at the bottom gives the smallest
+    /// such value; the lowest feasible `p` gives the
+    /// smallest overall.
+    fn next_bit_permutation_naive(x: u128, width: usize) -> Option<u128> {
+        let k = (0..width).filter(|&i| bit(x, i) == 1).count();
+        if k == 0 {
+            return None;
+        }
+        for p in 0..width {
+            if bit(x, p) == 1 {
+                continue;
+            }
+            let above = (p + 1..width).filter(|&i| bit(x, i) == 1).count();
+            if above + 1 > k || k - above - 1 > p {
+                continue;
+            }
+            let high: u128 = (p + 1..width).map(|i| bit(x, i) << i).sum();
+            let low: u128 = (0..k - above - 1).map(|i| 1 << i).sum();
+            return Some(high | (1 << p) | low);
+        }
+        None
+    }
+
+    fn extract_bits_naive(x: u128, width: usize, lo: usize, len: usize) -> u128 {
+        (lo..(lo + len).min(width))
+            .map(|i| bit(x, i) << (i - lo))
+            .sum()
+    }
+
+    /// Check every `Bits` operation for the given type
+    /// against the naive references on random inputs.
+    macro_rules! check_bits {
+        ($t:ty, $rng:expr) => {{
+            let width = <$t>::BITS as usize;
+            for _ in 0..1000 {
+                let x: $t = $rng.gen();
+                let lo = $rng.gen_range(0..width + 2);
+                let len = $rng.gen_range(0..width + 2);
+                let w = x as u128;
+                assert_eq!(popcount(x), popcount_naive_wide(w), "popcount {:x}", x);
+                assert_eq!(parity(x), parity_naive(w, width), "parity {:x}", x);
+                assert_eq!(trailing_zeros(x), trailing_zeros_naive(w, width));
+                assert_eq!(leading_zeros(x), leading_zeros_naive(w, width));
+                assert_eq!(reverse_bits(x) as u128, reverse_bits_naive(w, width));
+                assert_eq!(gray_code(x) as u128, gray_code_naive(w, width));
+                assert_eq!(gray_decode(x) as u128, gray_decode_naive(w, width));
+                assert_eq!(gray_decode(gray_code(x)), x);
+                assert_eq!(
+                    extract_bits(x, lo, len) as u128,
+                    extract_bits_naive(w, width, lo, len),
+                    "extract_bits {:x} {} {}",
+                    x,
+                    lo,
+                    len
+                );
+                assert_eq!(
+                    next_bit_permutation(x).map(|y| y as u128),
+                    next_bit_permutation_naive(w, width),
+                    "next_bit_permutation {:x}",
+                    x
+                );
+            }
+        }};
+    }
+
+    /// Naive popcount for any width.
+    fn popcount_naive_wide(x: u128) -> usize {
+        popcount_naive(x as u64) + popcount_naive((x >> 64) as u64)
+    }
+
+    #[test]
+    fn test_bits_random() {
+        let mut seed = [0; 32];
+        seed[0..4].copy_from_slice(&[0xb1, 0x75, 0xb1, 0x75]);
+        let mut rng = StdRng::from_seed(seed);
+        check_bits!(u8, rng);
+        check_bits!(u16, rng);
+        check_bits!(u32, rng);
+        check_bits!(u64, rng);
+        check_bits!(u128, rng);
+        check_bits!(usize, rng);
+    }
+
+    #[test]
+    fn test_next_bit_permutation_edges() {
+        assert_eq!(next_bit_permutation(0u8), None);
+        assert_eq!(next_bit_permutation(0x80u8), None);
+        assert_eq!(next_bit_permutation(0xffu8), None);
+        assert_eq!(next_bit_permutation(0xf0u8), None);
+        assert_eq!(next_bit_permutation(0x78u8), Some(0x87));
+        let mut x = 0b11u8;
+        let mut count = 1;
+        while let Some(y) = next_bit_permutation(x) {
+            assert!(y > x);
+            x = y;
+            count += 1;
+        }
+        assert_eq!(count, 28);
+    }
 }
@@ -0,0 +1,547 @@
+// Copyright © 2016 Bart Massey
+// This program is licensed under the "MIT License".
+// Please see the file COPYING in this distribution
+// for license terms.
+
+//! Bit sets for Advent of Code 2016 solutions.
+//!
+//! Many puzzle sets are drawn from a tiny universe: a
+//! handful of waypoints, a few floors' worth of devices. A
+//! `BitSet64` holds a set of numbers less than 64 in a
+//! single `u64`, so that it is cheap to copy, compare and
+//! hash. A `BitSet` holds a set of numbers of any size,
+//! growing as needed.
+//!
+//! Both implement `aoc::Set`, and also the operators `|`
+//! (union), `&` (intersection), `-` (difference) and `^`
+//! (symmetric difference). Both support rank (number of
+//! elements below a value) and select (the `k`th smallest
+//! element). Ordering compares the sets as binary numbers,
+//! with element `i` having value `2**i`.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use aoc::{BitSet, BitSet64, Set};
+//!
+//! let s: BitSet64 = aoc::make_set(&[3, 1, 4]);
+//! let t: BitSet64 = aoc::make_set(&[1, 5]);
+//! assert_eq!((s | t).elems().collect::<Vec<_>>(), vec![1, 3, 4, 5]);
+//! assert_eq!(s.len(), 3);
+//! assert!(!t.is_subset(&s));
+//! assert_eq!(s.rank(4), 2);
+//! assert_eq!(s.select(2), Some(4));
+//!
+//! let big: BitSet = aoc::make_set(&[1000, 3]);
+//! assert_eq!(big.elems().collect::<Vec<_>>(), vec![3, 1000]);
+//! assert!(big > BitSet::from(s));
+//! ```
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::{BitAnd, BitOr, BitXor, Sub};
+
+use crate::bits::popcount;
+use crate::sets::Set;
+
+/// Bits per word.
+const WORD: usize = 64;
+
+/// Return the position of the `k`th (from 0) one bit of
+/// `w`, if any.
+fn select_word(mut w: u64, k: usize) -> Option<usize> {
+    if popcount(w) <= k {
+        return None;
+    }
+    for _ in 0..k {
+        w &= w - 1;
+    }
+    Some(w.trailing_zeros() as usize)
+}
+
+/// Return the number of one bits of `w` below position
+/// `i`.
+fn rank_word(w: u64, i: usize) -> usize {
+    if i >= WORD {
+        popcount(w)
+    } else {
+        popcount(w & ((1 << i) - 1))
+    }
+}
+
+/// Set of numbers in `0..64`.
+#[derive(Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct BitSet64(u64);
+
+impl BitSet64 {
+    /// Number of possible elements.
+    pub const CAPACITY: usize = WORD;
+
+    /// The empty set.
+    pub const fn new() -> BitSet64 {
+        BitSet64(0)
+    }
+
+    /// The set whose elements are the one bits of `bits`.
+    pub const fn from_bits(bits: u64) -> BitSet64 {
+        BitSet64(bits)
+    }
+
+    /// The elements of the set, as one bits.
+    pub const fn bits(self) -> u64 {
+        self.0
+    }
+
+    /// Single-bit mask for an element.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the element is out of range.
+    fn mask(elem: usize) -> u64 {
+        assert!(
+            elem < Self::CAPACITY,
+            "bitset: element {} out of range",
+            elem
+        );
+        1 << elem
+    }
+
+    /// Elements in exactly one of the sets.
+    pub fn symmetric_difference(&self, other: &BitSet64) -> BitSet64 {
+        BitSet64(self.0 ^ other.0)
+    }
+
+    /// Number of elements less than `elem`.
+    pub fn rank(&self, elem: usize) -> usize {
+        rank_word(self.0, elem)
+    }
+
+    /// The `k`th smallest element, counting from 0, if
+    /// any.
+    pub fn select(&self, k: usize) -> Option<usize> {
+        select_word(self.0, k)
+    }
+}
+
+impl Set for BitSet64 {
+    type Elem = usize;
+    type Iter<'a> = BitSet64Iter;
+
+    /// # Panics
+    ///
+    /// Panics if the element is out of range.
+    fn insert(&mut self, elem: usize) -> bool {
+        let m = BitSet64::mask(elem);
+        let fresh = self.0 & m == 0;
+        self.0 |= m;
+        fresh
+    }
+
+    fn remove(&mut self, elem: &usize) -> bool {
+        if *elem >= BitSet64::CAPACITY {
+            return false;
+        }
+        let m = BitSet64::mask(*elem);
+        let present = self.0 & m != 0;
+        self.0 &= !m;
+        present
+    }
+
+    fn contains(&self, elem: &usize) -> bool {
+        *elem < BitSet64::CAPACITY && self.0 & BitSet64::mask(*elem) != 0
+    }
+
+    fn len(&self) -> usize {
+        popcount(self.0)
+    }
+
+    fn elems(&self) -> BitSet64Iter {
+        BitSet64Iter(self.0)
+    }
+
+    fn union(&self, other: &BitSet64) -> BitSet64 {
+        *self | *other
+    }
+
+    fn difference(&self, other: &BitSet64) -> BitSet64 {
+        *self - *other
+    }
+
+    fn intersection(&self, other: &BitSet64) -> BitSet64 {
+        *self & *other
+    }
+
+    fn is_subset(&self, other: &BitSet64) -> bool {
+        self.0 & !other.0 == 0
+    }
+}
+
+impl fmt::Debug for BitSet64 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_set().entries(self.elems()).finish()
+    }
+}
+
+impl FromIterator<usize> for BitSet64 {
+    fn from_iter<I: IntoIterator<Item = usize>>(elems: I) -> BitSet64 {
+        BitSet64::from_elems(elems)
+    }
+}
+
+impl BitOr for BitSet64 {
+    type Output = BitSet64;
+
+    fn bitor(self, other: BitSet64) -> BitSet64 {
+        BitSet64(self.0 | other.0)
+    }
+}
+
+impl BitAnd for BitSet64 {
+    type Output = BitSet64;
+
+    fn bitand(self, other: BitSet64) -> BitSet64 {
+        BitSet64(self.0 & other.0)
+    }
+}
+
+impl Sub for BitSet64 {
+    type Output = BitSet64;
+
+    fn sub(self, other: BitSet64) -> BitSet64 {
+        BitSet64(self.0 & !other.0)
+    }
+}
+
+impl BitXor for BitSet64 {
+    type Output = BitSet64;
+
+    fn bitxor(self, other: BitSet64) -> BitSet64 {
+        self.symmetric_difference(&other)
+    }
+}
+
+/// Iterator over the elements of a `BitSet64` in
+/// increasing order.
+#[derive(Clone, Debug)]
+pub struct BitSet64Iter(u64);
+
+impl Iterator for BitSet64Iter {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        if self.0 == 0 {
+            return None;
+        }
+        let elem = self.0.trailing_zeros() as usize;
+        self.0 &= self.0 - 1;
+        Some(elem)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let n = popcount(self.0);
+        (n, Some(n))
+    }
+}
+
+impl ExactSizeIterator for BitSet64Iter {}
+
+/// Growable set of natural numbers, stored as a vector of
+/// words. Trailing zero words are never stored, so that
+/// equal sets have equal representations.
+#[derive(Clone, Default, PartialEq, Eq, Hash)]
+pub struct BitSet {
+    /// Words of the set, least significant first.
+    words: Vec<u64>,
+}
+
+impl BitSet {
+    /// The empty set.
+    pub fn new() -> BitSet {
+        BitSet::default()
+    }
+
+    /// Drop trailing zero words.
+    fn trim(&mut self) {
+        while self.words.last() == Some(&0) {
+            self.words.pop();
+        }
+    }
+
+    /// Combine two sets word by word with the given
+    /// operation.
+    fn zip_words<F>(&self, other: &BitSet, op: F) -> BitSet
+    where
+        F: Fn(u64, u64) -> u64,
+    {
+        let n = self.words.len().max(other.words.len());
+        let word = |ws: &[u64], i: usize| ws.get(i).cloned().unwrap_or(0);
+        let mut result = BitSet {
+            words: (0..n)
+                .map(|i| op(word(&self.words, i), word(&other.words, i)))
+                .collect(),
+        };
+        result.trim();
+        result
+    }
+
+    /// Elements in exactly one of the sets.
+    pub fn symmetric_difference(&self, other: &BitSet) -> BitSet {
+        self.zip_words(other, |a, b| a ^ b)
+    }
+
+    /// Number of elements less than `elem`.
+    pub fn rank(&self, elem: usize) -> usize {
+        let (w, b) = (elem / WORD, elem % WORD);
+        let below: usize = self.words.iter().take(w).map(|&x| popcount(x)).sum();
+        below + self.words.get(w).map_or(0, |&x| rank_word(x, b))
+    }
+
+    /// The `k`th smallest element, counting from 0, if
+    /// any.
+    pub fn select(&self, mut k: usize) -> Option<usize> {
+        for (i, &w) in self.words.iter().enumerate() {
+            let n = popcount(w);
+            if k < n {
+                return select_word(w, k).map(|b| i * WORD + b);
+            }
+            k -= n;
+        }
+        None
+    }
+}
+
+impl Set for BitSet {
+    type Elem = usize;
+    type Iter<'a> = BitSetIter<'a>;
+
+    fn insert(&mut self, elem: usize) -> bool {
+        let (w, b) = (elem / WORD, elem % WORD);
+        if w >= self.words.len() {
+            self.words.resize(w + 1, 0);
+        }
+        let fresh = self.words[w] & (1 << b) == 0;
+        self.words[w] |= 1 << b;
+        fresh
+    }
+
+    fn remove(&mut self, elem: &usize) -> bool {
+        let (w, b) = (elem / WORD, elem % WORD);
+        if !self.contains(elem) {
+            return false;
+        }
+        self.words[w] &= !(1 << b);
+        self.trim();
+        true
+    }
+
+    fn contains(&self, elem: &usize) -> bool {
+        let (w, b) = (elem / WORD, elem % WORD);
+        self.words.get(w).is_some_and(|&x| x & (1 << b) != 0)
+    }
+
+    fn len(&self) -> usize {
+        self.words.iter().map(|&w| popcount(w)).sum()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+
+    fn elems(&self) -> BitSetIter<'_> {
+        BitSetIter {
+            words: &self.words,
+            index: 0,
+            current: self.words.first().cloned().unwrap_or(0),
+        }
+    }
+
+    fn union(&self, other: &BitSet) -> BitSet {
+        self.zip_words(other, |a, b| a | b)
+    }
+
+    fn difference(&self, other: &BitSet) -> BitSet {
+        self.zip_words(other, |a, b| a & !b)
+    }
+
+    fn intersection(&self, other: &BitSet) -> BitSet {
+        self.zip_words(other, |a, b| a & b)
+    }
+
+    fn is_subset(&self, other: &BitSet) -> bool {
+        self.difference(other).is_empty()
+    }
+}
+
+impl Ord for BitSet {
+    /// Compare as binary numbers.
+    fn cmp(&self, other: &BitSet) -> Ordering {
+        self.words
+            .len()
+            .cmp(&other.words.len())
+            .then_with(|| self.words.iter().rev().cmp(other.words.iter().rev()))
+    }
+}
+
+impl PartialOrd for BitSet {
+    fn partial_cmp(&self, other: &BitSet) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Debug for BitSet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_set().entries(self.elems()).finish()
+    }
+}
+
+impl FromIterator<usize> for BitSet {
+    fn from_iter<I: IntoIterator<Item = usize>>(elems: I) -> BitSet {
+        BitSet::from_elems(elems)
+    }
+}
+
+impl From<BitSet64> for BitSet {
+    fn from(s: BitSet64) -> BitSet {
+        let mut result = BitSet {
+            words: vec![s.bits()],
+        };
+        result.trim();
+        result
+    }
+}
+
+impl BitOr for &BitSet {
+    type Output = BitSet;
+
+    fn bitor(self, other: &BitSet) -> BitSet {
+        self.union(other)
+    }
+}
+
+impl BitAnd for &BitSet {
+    type Output = BitSet;
+
+    fn bitand(self, other: &BitSet) -> BitSet {
+        self.intersection(other)
+    }
+}
+
+impl Sub for &BitSet {
+    type Output = BitSet;
+
+    fn sub(self, other: &BitSet) -> BitSet {
+        self.difference(other)
+    }
+}
+
+impl BitXor for &BitSet {
+    type Output = BitSet;
+
+    fn bitxor(self, other: &BitSet) -> BitSet {
+        self.symmetric_difference(other)
+    }
+}
+
+/// Iterator over the elements of a `BitSet` in increasing
+/// order.
+#[derive(Clone, Debug)]
+pub struct BitSetIter<'a> {
+    /// Words of the set.
+    words: &'a [u64],
+    /// Index of the current word.
+    index: usize,
+    /// Remaining bits of the current word.
+    current: u64,
+}
+
+impl Iterator for BitSetIter<'_> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        while self.current == 0 {
+            self.index += 1;
+            self.current = *self.words.get(self.index)?;
+        }
+        let b = self.current.trailing_zeros() as usize;
+        self.current &= self.current - 1;
+        Some(self.index * WORD + b)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    extern crate rand;
+
+    use self::rand::prelude::*;
+    use super::*;
+    use std::collections::BTreeSet;
+
+    /// Random sets of numbers below `limit`.
+    fn random_sets(limit: usize) -> Vec<BTreeSet<usize>> {
+        let mut seed = [0; 32];
+        seed[0..4].copy_from_slice(&[0x0b, 0x17, 0x5e, 0x75]);
+        let mut rng = StdRng::from_seed(seed);
+        (0..50)
+            .map(|_| {
+                let n = rng.gen_range(0..20);
+                (0..n).map(|_| rng.gen_range(0..limit)).collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn bitset_matches_btreeset() {
+        let sets = random_sets(300);
+        for (a, b) in sets.iter().zip(sets.iter().skip(1)) {
+            let ba: BitSet = a.iter().cloned().collect();
+            let bb: BitSet = b.iter().cloned().collect();
+            let check = |s: BitSet, t: BTreeSet<usize>| {
+                assert_eq!(
+                    s.elems().collect::<Vec<_>>(),
+                    t.into_iter().collect::<Vec<_>>()
+                );
+            };
+            check(&ba | &bb, a.union(b).cloned().collect());
+            check(&ba & &bb, a.intersection(b).cloned().collect());
+            check(&ba - &bb, a.difference(b).cloned().collect());
+            check(&ba ^ &bb, a.symmetric_difference(b).cloned().collect());
+            assert_eq!(ba.len(), a.len());
+            assert_eq!(ba.is_subset(&bb), a.is_subset(b));
+            for (k, &e) in a.iter().enumerate() {
+                assert_eq!(ba.select(k), Some(e));
+                assert_eq!(ba.rank(e), k);
+            }
+            assert_eq!(ba.select(a.len()), None);
+        }
+    }
+
+    #[test]
+    fn bitset64_matches_bitset() {
+        let sets = random_sets(64);
+        for (a, b) in sets.iter().zip(sets.iter().skip(1)) {
+            let sa: BitSet64 = a.iter().cloned().collect();
+            let sb: BitSet64 = b.iter().cloned().collect();
+            let ba = BitSet::from(sa);
+            let bb = BitSet::from(sb);
+            assert_eq!(BitSet::from(sa | sb), &ba | &bb);
+            assert_eq!(BitSet::from(sa - sb), &ba - &bb);
+            assert_eq!(sa.cmp(&sb), ba.cmp(&bb));
+            for e in 0..=64 {
+                assert_eq!(sa.rank(e), ba.rank(e));
+            }
+        }
+    }
+
+    #[test]
+    fn bitset_trims() {
+        let mut s: BitSet = crate::make_set(&[500]);
+        assert!(s.remove(&500));
+        assert_eq!(s, BitSet::new());
+        assert!(s.is_empty());
+        let t: BitSet = crate::make_set(&[1, 200]);
+        let u: BitSet = crate::make_set(&[200]);
+        assert_eq!(&t & &u, u);
+        assert!(t > u);
+    }
+}
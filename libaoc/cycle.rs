@@ -0,0 +1,222 @@
+// Copyright © 2016 Bart Massey
+// This program is licensed under the "MIT License".
+// Please see the file COPYING in this distribution
+// for license terms.
+
+//! Cycle detection for deterministic iterations.
+//!
+//! Iterating a function on a finite state space must
+//! eventually repeat a state, after which the sequence is
+//! periodic. A `Cycle` describes that shape: some `prefix`
+//! number of states that appear only once, followed by a
+//! loop of `period` states that repeats forever.
+//!
+//! `find_cycle()` uses Brent's algorithm, which needs only
+//! equality on states and keeps just two of them around;
+//! `find_cycle_within()` gives up after a given number of
+//! steps, for sequences whose period may be too long to be
+//! worth finding.
+//! `find_cycle_hashed()` remembers every state it sees in a
+//! hash map, which costs memory but takes just one step per
+//! state. `iterate_n()` finds the `n`th iterate without
+//! taking `n` steps when the sequence repeats early, using
+//! `find_cycle_within()` so that it needs no more memory
+//! when it does not.
+//!
+//! `find_cycle()` and `find_cycle_hashed()` loop forever on
+//! a sequence that never repeats.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use aoc::Cycle;
+//!
+//! // 1, 2, 4, 8, 16, 32, 64, 28, 56, 12, 24, 48, 96, 92, 84,
+//! // 68, 36, 72, 44, 88, 76, 52, 4, ...
+//! let step = |x: &u64| x * 2 % 100;
+//! let cycle = aoc::find_cycle(1, step);
+//! assert_eq!(cycle, Cycle { prefix: 2, period: 20 });
+//! assert_eq!(aoc::find_cycle_hashed(1, step), cycle);
+//! assert_eq!(aoc::find_cycle_within(1, step, 10), None);
+//! assert_eq!(aoc::iterate_n(1, step, 1_000_000_000_000), 76);
+//! ```
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// Shape of an eventually-periodic sequence `x0, x1, ...`:
+/// `x[i] == x[i + period]` exactly when `i >= prefix`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Cycle {
+    /// Number of states before the loop starts.
+    pub prefix: usize,
+    /// Number of states in the loop.
+    pub period: usize,
+}
+
+impl Cycle {
+    /// Index of the earliest state equal to state `n` of
+    /// the sequence.
+    pub fn reduce(&self, n: usize) -> usize {
+        if n < self.prefix {
+            n
+        } else {
+            self.prefix + (n - self.prefix) % self.period
+        }
+    }
+}
+
+/// Find the cycle of the sequence starting at `initial`
+/// and proceeding by `step`, using Brent's algorithm.
+pub fn find_cycle<T, F>(initial: T, step: F) -> Cycle
+where
+    T: Clone + PartialEq,
+    F: FnMut(&T) -> T,
+{
+    find_cycle_within(initial, step, usize::MAX).expect("find_cycle: step limit reached")
+}
+
+/// Find the cycle of the sequence starting at `initial`
+/// and proceeding by `step`, using Brent's algorithm, but
+/// give up and return `None` if the search for the period
+/// takes more than `limit` steps. A cycle is always found
+/// when `3 * (prefix + period) <= limit`.
+pub fn find_cycle_within<T, F>(initial: T, mut step: F, limit: usize) -> Option<Cycle>
+where
+    T: Clone + PartialEq,
+    F: FnMut(&T) -> T,
+{
+    // Find the period: race a hare ahead of a tortoise that
+    // teleports to the hare at each power of two.
+    let mut power = 1;
+    let mut period = 1;
+    let mut nsteps = 1;
+    let mut tortoise = initial.clone();
+    let mut hare = step(&initial);
+    while tortoise != hare {
+        if nsteps >= limit {
+            return None;
+        }
+        if power == period {
+            tortoise = hare.clone();
+            power *= 2;
+            period = 0;
+        }
+        hare = step(&hare);
+        period += 1;
+        nsteps += 1;
+    }
+
+    // Find the prefix: start the hare a period ahead and
+    // walk both until they meet at the start of the loop.
+    let mut tortoise = initial.clone();
+    let mut hare = initial;
+    for _ in 0..period {
+        hare = step(&hare);
+    }
+    let mut prefix = 0;
+    while tortoise != hare {
+        tortoise = step(&tortoise);
+        hare = step(&hare);
+        prefix += 1;
+    }
+    Some(Cycle { prefix, period })
+}
+
+/// Find the cycle of the sequence starting at `initial`
+/// and proceeding by `step`, remembering every state seen.
+pub fn find_cycle_hashed<T, F>(initial: T, mut step: F) -> Cycle
+where
+    T: Clone + Eq + Hash,
+    F: FnMut(&T) -> T,
+{
+    let mut seen = HashMap::new();
+    let mut cur = initial;
+    loop {
+        if let Some(&prefix) = seen.get(&cur) {
+            let period = seen.len() - prefix;
+            return Cycle { prefix, period };
+        }
+        let next = step(&cur);
+        seen.insert(cur, seen.len());
+        cur = next;
+    }
+}
+
+/// Return state `n` of the sequence starting at `initial`
+/// and proceeding by `step`: that is, `step` applied `n`
+/// times. If `find_cycle_within()` finds a cycle within `n`
+/// steps, jump ahead by whole periods; otherwise just step.
+/// Either way only a couple of states are kept, but `step`
+/// may be called up to about `3 * n` times.
+pub fn iterate_n<T, F>(initial: T, mut step: F, n: usize) -> T
+where
+    T: Clone + PartialEq,
+    F: FnMut(&T) -> T,
+{
+    let nsteps = match find_cycle_within(initial.clone(), &mut step, n) {
+        Some(cycle) => cycle.reduce(n),
+        None => n,
+    };
+    let mut cur = initial;
+    for _ in 0..nsteps {
+        cur = step(&cur);
+    }
+    cur
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Naive state `n` of the sequence.
+    fn nth<F: FnMut(&u64) -> u64>(mut x: u64, mut step: F, n: usize) -> u64 {
+        for _ in 0..n {
+            x = step(&x);
+        }
+        x
+    }
+
+    #[test]
+    fn test_cycles_agree() {
+        for modulus in 1..60 {
+            for mult in 1..10 {
+                for x0 in 0..modulus {
+                    let step = |x: &u64| (x * mult + 3) % modulus;
+                    let cycle = find_cycle(x0, step);
+                    assert_eq!(find_cycle_hashed(x0, step), cycle);
+                    let xp = nth(x0, step, cycle.prefix);
+                    assert_eq!(nth(xp, step, cycle.period), xp);
+                    if cycle.prefix > 0 {
+                        let xq = nth(x0, step, cycle.prefix - 1);
+                        assert_ne!(nth(xq, step, cycle.period), xq);
+                    }
+                    let limit = 3 * (cycle.prefix + cycle.period);
+                    assert_eq!(find_cycle_within(x0, step, limit), Some(cycle));
+                    for n in [0, 1, 5, 77, 1000] {
+                        assert_eq!(iterate_n(x0, step, n), nth(x0, step, n));
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_fixed_point() {
+        let cycle = find_cycle(10u64, |&x| x / 2);
+        assert_eq!(
+            cycle,
+            Cycle {
+                prefix: 4,
+                period: 1
+            }
+        );
+        assert_eq!(cycle.reduce(1_000_000), 4);
+        assert_eq!(iterate_n(10u64, |&x| x / 2, 1_000_000), 0);
+    }
+
+    #[test]
+    fn test_no_cycle() {
+        assert_eq!(iterate_n(0u64, |&x| x + 1, 1_000_000), 1_000_000);
+    }
+}
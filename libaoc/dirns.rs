@@ -7,7 +7,16 @@
 //!
 //! To use this, make a new `GridBox` to set clipping bounds,
 //! then call the `neighbors()` method of the `ClipBox` to get
-//! an iterator over clipped neighbors in cardinal directions.
+//! an iterator over clipped neighbors in a given
+//! `Neighborhood`: the cardinal directions, all eight
+//! directions, or hex directions. 3D locations use the 3D
+//! equivalents in `Neighborhood3` instead, so asking for a
+//! neighborhood of the wrong dimension will not compile.
+//!
+//! Locations may be any `GridPoint`: pairs or triples of
+//! `usize` or `isize`, or the signed `SPoint`. Unsigned
+//! locations are clipped at zero; signed locations may go
+//! negative on an `Unclipped` grid.
 //!
 //! # Examples
 //!
@@ -15,23 +24,226 @@
 //! use aoc::dirns::*;
 //!
 //! let clip_box = GridBox::new(3, 4);
-//! let neighbors = clip_box.neighbors((2, 0))
+//! let neighbors = clip_box.neighbors((2, 0), VonNeumann)
 //!                 .collect::<Vec<_>>();
 //! assert_eq!(neighbors, vec![(2, 1), (1, 0)]);
+//!
+//! let grid_box = GridBox::new_grid();
+//! let neighbors = grid_box.neighbors((0isize, 0, 0), Moore3);
+//! assert_eq!(neighbors.count(), 26);
 //! ```
+//!
+//! For puzzles with a map, a `Grid` holds the map contents
+//! and knows its own clip box.
+//!
+//! ```rust
+//! use aoc::dirns::*;
+//!
+//! let lines = vec!["#.#".to_string(), "..#".to_string()];
+//! let grid = Grid::from_lines(lines, |c| c == '.');
+//! assert_eq!(grid.width(), 3);
+//! assert!(grid[(0, 1)]);
+//! let open = grid.neighbors((1, 1), VonNeumann)
+//!     .filter(|&p| grid[p])
+//!     .collect::<Vec<_>>();
+//! assert_eq!(open, vec![(1, 0), (0, 1)]);
+//! assert_eq!(grid.render(|_, &c| if c { '.' } else { '#' }), "#.#\n..#\n");
+//! ```
+
+use std::cmp::max;
+use std::collections::HashSet;
+use std::fmt::{self, Display, Write};
+use std::num::TryFromIntError;
+use std::ops::{Add, AddAssign, Index, IndexMut, Mul, Neg, Sub, SubAssign};
 
 /// The cardinal directions: up, down, left, right in
 /// an x-y coordinate system where increasing y is down.
 pub static DIRNS: [(isize, isize); 4] = [(0, -1), (0, 1), (-1, 0), (1, 0)];
 
+/// The cardinal directions followed by the diagonals.
+pub static MOORE_DIRNS: [(isize, isize); 8] = [
+    (0, -1),
+    (0, 1),
+    (-1, 0),
+    (1, 0),
+    (-1, -1),
+    (1, -1),
+    (-1, 1),
+    (1, 1),
+];
+
+/// The six directions in [axial hex coordinates][1]
+/// `(q, r)`, counterclockwise from `+q`.
+///
+/// [1]: https://www.redblobgames.com/grids/hexagons/#coordinates-axial
+pub static HEX_DIRNS: [(isize, isize); 6] = [(1, 0), (1, -1), (0, -1), (-1, 0), (-1, 1), (0, 1)];
+
+/// The six face directions in x-y-z space.
+pub static DIRNS_3D: [(isize, isize, isize); 6] = [
+    (0, -1, 0),
+    (0, 1, 0),
+    (-1, 0, 0),
+    (1, 0, 0),
+    (0, 0, -1),
+    (0, 0, 1),
+];
+
+/// All 26 directions in x-y-z space: faces, then edges,
+/// then corners.
+pub static MOORE_DIRNS_3D: [(isize, isize, isize); 26] = [
+    (0, -1, 0),
+    (0, 1, 0),
+    (-1, 0, 0),
+    (1, 0, 0),
+    (0, 0, -1),
+    (0, 0, 1),
+    (-1, -1, 0),
+    (1, -1, 0),
+    (-1, 1, 0),
+    (1, 1, 0),
+    (-1, 0, -1),
+    (1, 0, -1),
+    (-1, 0, 1),
+    (1, 0, 1),
+    (0, -1, -1),
+    (0, 1, -1),
+    (0, -1, 1),
+    (0, 1, 1),
+    (-1, -1, -1),
+    (1, -1, -1),
+    (-1, 1, -1),
+    (1, 1, -1),
+    (-1, -1, 1),
+    (1, -1, 1),
+    (-1, 1, 1),
+    (1, 1, 1),
+];
+
 /// Type of unsigned coordinates.
 pub type Point = (usize, usize);
 
+/// Which locations count as neighbors in 2D.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Neighborhood {
+    /// The four cardinal directions.
+    VonNeumann,
+    /// The eight cardinal and diagonal directions.
+    Moore,
+    /// The six directions in axial hex coordinates.
+    HexAxial,
+}
+
+pub use self::Neighborhood::*;
+
+/// Which locations count as neighbors in 3D.
+///
+/// A 3D neighborhood cannot be used with 2D locations:
+///
+/// ```compile_fail
+/// use aoc::dirns::*;
+///
+/// GridBox::new(2, 2).neighbors((0, 0), Moore3);
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Neighborhood3 {
+    /// The six face directions.
+    VonNeumann3,
+    /// The 26 face, edge and corner directions.
+    Moore3,
+}
+
+pub use self::Neighborhood3::*;
+
+/// Coordinates usable with a `GridBox`.
+pub trait GridPoint: Copy {
+    /// Signed offset between locations.
+    type Offset: Copy + 'static;
+
+    /// Neighborhoods of the right dimension.
+    type Neighborhood: Copy;
+
+    /// Return the offsets of the given neighborhood.
+    fn offsets(nbhd: Self::Neighborhood) -> &'static [Self::Offset];
+
+    /// Return this location moved by the given offset, or
+    /// `None` if the result is not representable.
+    fn offset_by(self, off: Self::Offset) -> Option<Self>;
+
+    /// Return true iff every coordinate of this location
+    /// is nonnegative and less than the corresponding
+    /// coordinate of `size`.
+    fn within(self, size: Self) -> bool;
+}
+
+/// Implement `GridPoint` for a 2D coordinate pair.
+macro_rules! grid_point_2d {
+    ($t:ty, $add:ident) => {
+        impl GridPoint for ($t, $t) {
+            type Offset = (isize, isize);
+            type Neighborhood = Neighborhood;
+
+            fn offsets(nbhd: Neighborhood) -> &'static [(isize, isize)] {
+                match nbhd {
+                    VonNeumann => &DIRNS,
+                    Moore => &MOORE_DIRNS,
+                    HexAxial => &HEX_DIRNS,
+                }
+            }
+
+            fn offset_by(self, (dx, dy): (isize, isize)) -> Option<Self> {
+                Some((self.0.$add(dx)?, self.1.$add(dy)?))
+            }
+
+            #[allow(unused_comparisons)]
+            fn within(self, (x_size, y_size): Self) -> bool {
+                self.0 >= 0 && self.1 >= 0 && self.0 < x_size && self.1 < y_size
+            }
+        }
+    };
+}
+
+grid_point_2d!(usize, checked_add_signed);
+grid_point_2d!(isize, checked_add);
+
+/// Implement `GridPoint` for a 3D coordinate triple.
+macro_rules! grid_point_3d {
+    ($t:ty, $add:ident) => {
+        impl GridPoint for ($t, $t, $t) {
+            type Offset = (isize, isize, isize);
+            type Neighborhood = Neighborhood3;
+
+            fn offsets(nbhd: Neighborhood3) -> &'static [(isize, isize, isize)] {
+                match nbhd {
+                    VonNeumann3 => &DIRNS_3D,
+                    Moore3 => &MOORE_DIRNS_3D,
+                }
+            }
+
+            fn offset_by(self, (dx, dy, dz): (isize, isize, isize)) -> Option<Self> {
+                Some((self.0.$add(dx)?, self.1.$add(dy)?, self.2.$add(dz)?))
+            }
+
+            #[allow(unused_comparisons)]
+            fn within(self, size: Self) -> bool {
+                self.0 >= 0
+                    && self.1 >= 0
+                    && self.2 >= 0
+                    && self.0 < size.0
+                    && self.1 < size.1
+                    && self.2 < size.2
+            }
+        }
+    };
+}
+
+grid_point_3d!(usize, checked_add_signed);
+grid_point_3d!(isize, checked_add);
+
 /// Description of the grid, for possible clipping.
 #[derive(Copy, Clone)]
-pub enum GridBox {
-    /// Grid is clipped on bottom and right.
-    ClipBox(Point),
+pub enum GridBox<P: GridPoint = Point> {
+    /// Grid is clipped at zero and at the given size.
+    ClipBox(P),
     /// Grid is unclipped.
     Unclipped,
 }
@@ -44,85 +256,75 @@ impl GridBox {
     pub fn new(x_size: usize, y_size: usize) -> GridBox {
         ClipBox((x_size, y_size))
     }
+}
 
+impl<P: GridPoint> GridBox<P> {
     /// Create an "unbounded clip box" for neighbor
-    /// calculations.  Negative locations will still be
-    /// clipped.
-    pub fn new_grid() -> GridBox {
+    /// calculations.  Unsigned locations will still be
+    /// clipped at zero.
+    pub fn new_grid() -> GridBox<P> {
         Unclipped
     }
 
     /// Return an iterator that will produce the neighbors
-    /// of the given location, clipped as needed.
-    pub fn neighbors(&self, location: Point) -> Neighbors {
-        if let ClipBox((x_size, y_size)) = *self {
-            let (x, y) = location;
-            assert!(x < x_size && y < y_size);
+    /// of the given location in the given neighborhood,
+    /// clipped as needed.
+    pub fn neighbors(&self, location: P, nbhd: P::Neighborhood) -> Neighbors<P> {
+        if let ClipBox(size) = *self {
+            assert!(location.within(size));
         };
-        Neighbors::new(*self, location)
+        Neighbors::new(*self, location, nbhd)
     }
 
     /// Return the source location adjusted by the given offset
     /// iff the dest location is in-bounds. This is useful when
     /// "manual" clipping is needed.
-    pub fn clip(&self, loc: Point, off: (isize, isize)) -> Option<Point> {
-        let (x, y) = loc;
-        let (dx, dy) = off;
-        let nx = x as isize + dx;
-        let ny = y as isize + dy;
-        if nx < 0 || ny < 0 {
-            return None;
-        }
-        if let ClipBox((x_size, y_size)) = *self {
-            if nx >= x_size as isize || ny >= y_size as isize {
+    pub fn clip(&self, loc: P, off: P::Offset) -> Option<P> {
+        let n = loc.offset_by(off)?;
+        if let ClipBox(size) = *self {
+            if !n.within(size) {
                 return None;
             }
         };
-        Some((nx as usize, ny as usize))
+        Some(n)
     }
 }
 
-/// Iterator over the neighbors of a point in the four cardinal
-/// directions, clipped as appropriate.
-pub struct Neighbors {
+/// Iterator over the neighbors of a point in a given
+/// neighborhood, clipped as appropriate.
+pub struct Neighbors<P: GridPoint = Point> {
     /// Possible upper bounds on neighbor location.
-    bounds: GridBox,
+    bounds: GridBox<P>,
     /// Source location.
-    loc: Point,
-    /// Iterator for cardinal directions.
-    dirns: Box<dyn Iterator<Item = &'static (isize, isize)>>,
+    loc: P,
+    /// Iterator over neighborhood offsets.
+    dirns: std::slice::Iter<'static, P::Offset>,
 }
 
-impl Neighbors {
+impl<P: GridPoint> Neighbors<P> {
     /// Return an iterator over the neighbors of
     /// the given grid box starting at the given location.
-    pub fn new(grid_box: GridBox, location: Point) -> Self {
+    pub fn new(grid_box: GridBox<P>, location: P, nbhd: P::Neighborhood) -> Self {
         Neighbors {
             bounds: grid_box,
             loc: location,
-            dirns: Box::new(DIRNS.iter()),
+            dirns: P::offsets(nbhd).iter(),
         }
     }
 }
 
-impl Iterator for Neighbors {
-    type Item = Point;
+impl<P: GridPoint> Iterator for Neighbors<P> {
+    type Item = P;
 
-    /// Return the next cardinal neighbor of the source point,
+    /// Return the next neighbor of the source point,
     /// clipped as needed.
-    fn next(&mut self) -> Option<Point> {
-        loop {
-            match self.dirns.next() {
-                Some(&d) => {
-                    if let Some(n) = self.bounds.clip(self.loc, d) {
-                        return Some(n);
-                    }
-                }
-                None => {
-                    return None;
-                }
+    fn next(&mut self) -> Option<P> {
+        for &d in self.dirns.by_ref() {
+            if let Some(n) = self.bounds.clip(self.loc, d) {
+                return Some(n);
             }
         }
+        None
     }
 }
 
@@ -134,3 +336,681 @@ pub fn manhattan_distance(p1: Point, p2: Point) -> usize {
     let dy = (p1.1 as isize - p2.1 as isize).abs();
     (dx + dy) as usize
 }
+
+/// The ["Chebyshev Distance"][1] between two points: the
+/// number of king moves between them.
+///
+/// [1]: http://en.wikipedia.org/wiki/Chebyshev_distance
+pub fn chebyshev_distance(p1: Point, p2: Point) -> usize {
+    let dx = (p1.0 as isize - p2.0 as isize).abs();
+    let dy = (p1.1 as isize - p2.1 as isize).abs();
+    max(dx, dy) as usize
+}
+
+/// Signed point, unbounded in every direction. As with
+/// `DIRNS`, increasing y is down.
+///
+/// Points are also offsets: they can be added,
+/// subtracted, negated, scaled and rotated.
+/// Conversions to and from `Point` are checked.
+///
+/// # Examples
+///
+/// ```rust
+/// use aoc::dirns::*;
+///
+/// let up = SPoint::new(0, -1);
+/// let p = SPoint::ORIGIN + up.turn_left() * 3 + up;
+/// assert_eq!(p, SPoint::new(-3, -1));
+/// assert_eq!(p.manhattan_distance(SPoint::ORIGIN), 4);
+/// assert!(Point::try_from(p).is_err());
+/// assert_eq!(Point::try_from(-p), Ok((3, 1)));
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SPoint {
+    /// Horizontal coordinate.
+    pub x: isize,
+    /// Vertical coordinate.
+    pub y: isize,
+}
+
+impl SPoint {
+    /// The origin.
+    pub const ORIGIN: SPoint = SPoint { x: 0, y: 0 };
+
+    /// Make a new point.
+    pub const fn new(x: isize, y: isize) -> SPoint {
+        SPoint { x, y }
+    }
+
+    /// Rotate a quarter turn counterclockwise (as seen on
+    /// screen) about the origin: up becomes left.
+    pub fn turn_left(self) -> SPoint {
+        SPoint::new(self.y, -self.x)
+    }
+
+    /// Rotate a quarter turn clockwise (as seen on screen)
+    /// about the origin: up becomes right.
+    pub fn turn_right(self) -> SPoint {
+        SPoint::new(-self.y, self.x)
+    }
+
+    /// The Manhattan distance to the given point. See
+    /// `manhattan_distance()`.
+    pub fn manhattan_distance(self, other: SPoint) -> usize {
+        (self.x - other.x).unsigned_abs() + (self.y - other.y).unsigned_abs()
+    }
+
+    /// The Chebyshev distance to the given point. See
+    /// `chebyshev_distance()`.
+    pub fn chebyshev_distance(self, other: SPoint) -> usize {
+        max(
+            (self.x - other.x).unsigned_abs(),
+            (self.y - other.y).unsigned_abs(),
+        )
+    }
+}
+
+impl Add for SPoint {
+    type Output = SPoint;
+
+    fn add(self, other: SPoint) -> SPoint {
+        SPoint::new(self.x + other.x, self.y + other.y)
+    }
+}
+
+impl AddAssign for SPoint {
+    fn add_assign(&mut self, other: SPoint) {
+        *self = *self + other;
+    }
+}
+
+impl Sub for SPoint {
+    type Output = SPoint;
+
+    fn sub(self, other: SPoint) -> SPoint {
+        SPoint::new(self.x - other.x, self.y - other.y)
+    }
+}
+
+impl SubAssign for SPoint {
+    fn sub_assign(&mut self, other: SPoint) {
+        *self = *self - other;
+    }
+}
+
+impl Neg for SPoint {
+    type Output = SPoint;
+
+    fn neg(self) -> SPoint {
+        SPoint::new(-self.x, -self.y)
+    }
+}
+
+impl Mul<isize> for SPoint {
+    type Output = SPoint;
+
+    /// Scale both coordinates.
+    fn mul(self, k: isize) -> SPoint {
+        SPoint::new(self.x * k, self.y * k)
+    }
+}
+
+impl From<(isize, isize)> for SPoint {
+    fn from((x, y): (isize, isize)) -> SPoint {
+        SPoint::new(x, y)
+    }
+}
+
+impl From<SPoint> for (isize, isize) {
+    fn from(p: SPoint) -> (isize, isize) {
+        (p.x, p.y)
+    }
+}
+
+impl TryFrom<Point> for SPoint {
+    type Error = TryFromIntError;
+
+    /// Fails if a coordinate is too large.
+    fn try_from((x, y): Point) -> Result<SPoint, TryFromIntError> {
+        Ok(SPoint::new(isize::try_from(x)?, isize::try_from(y)?))
+    }
+}
+
+impl TryFrom<SPoint> for Point {
+    type Error = TryFromIntError;
+
+    /// Fails if a coordinate is negative.
+    fn try_from(p: SPoint) -> Result<Point, TryFromIntError> {
+        Ok((usize::try_from(p.x)?, usize::try_from(p.y)?))
+    }
+}
+
+/// Signed points may go negative on an `Unclipped` grid.
+impl GridPoint for SPoint {
+    type Offset = (isize, isize);
+    type Neighborhood = Neighborhood;
+
+    fn offsets(nbhd: Neighborhood) -> &'static [(isize, isize)] {
+        <(isize, isize)>::offsets(nbhd)
+    }
+
+    fn offset_by(self, (dx, dy): (isize, isize)) -> Option<SPoint> {
+        Some(SPoint::new(
+            self.x.checked_add(dx)?,
+            self.y.checked_add(dy)?,
+        ))
+    }
+
+    fn within(self, size: SPoint) -> bool {
+        (self.x, self.y).within((size.x, size.y))
+    }
+}
+
+/// Compass heading, with north up.
+///
+/// # Examples
+///
+/// ```rust
+/// use aoc::dirns::*;
+///
+/// let mut turtle = Turtle::new(SPoint::ORIGIN, Heading::North);
+/// let mut trail = Trail::new(SPoint::ORIGIN);
+/// for insn in "R8 R4 R4 R8".split(' ') {
+///     let turn = Turn::from_letter(insn.chars().next().unwrap()).unwrap();
+///     turtle.turn(turn);
+///     turtle.walk(insn[1..].parse().unwrap(), |p| trail.visit(p));
+/// }
+/// assert_eq!(trail.first_crossing(), Some(SPoint::new(4, 0)));
+/// assert_eq!(turtle.heading, Heading::North);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Heading {
+    /// Up: decreasing y.
+    North,
+    /// Right: increasing x.
+    East,
+    /// Down: increasing y.
+    South,
+    /// Left: decreasing x.
+    West,
+}
+
+impl Heading {
+    /// Headings in clockwise order from north.
+    pub const ALL: [Heading; 4] = [Heading::North, Heading::East, Heading::South, Heading::West];
+
+    /// Parse a direction letter: `N`/`U`, `E`/`R`, `S`/`D`
+    /// or `W`/`L`, either case.
+    pub fn from_letter(c: char) -> Option<Heading> {
+        match c.to_ascii_uppercase() {
+            'N' | 'U' => Some(Heading::North),
+            'E' | 'R' => Some(Heading::East),
+            'S' | 'D' => Some(Heading::South),
+            'W' | 'L' => Some(Heading::West),
+            _ => None,
+        }
+    }
+
+    /// Unit offset of a step in this heading.
+    pub fn offset(self) -> SPoint {
+        match self {
+            Heading::North => SPoint::new(0, -1),
+            Heading::East => SPoint::new(1, 0),
+            Heading::South => SPoint::new(0, 1),
+            Heading::West => SPoint::new(-1, 0),
+        }
+    }
+
+    /// Heading after the given turn.
+    pub fn turn(self, turn: Turn) -> Heading {
+        let quarters = match turn {
+            Turn::Left => 3,
+            Turn::Right => 1,
+            Turn::Reverse => 2,
+        };
+        Heading::ALL[(self as usize + quarters) % 4]
+    }
+}
+
+/// Change of heading.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Turn {
+    /// Quarter turn counterclockwise.
+    Left,
+    /// Quarter turn clockwise.
+    Right,
+    /// U-turn.
+    Reverse,
+}
+
+impl Turn {
+    /// Parse a turn letter: `L`, `R` or `U` (U-turn),
+    /// either case.
+    pub fn from_letter(c: char) -> Option<Turn> {
+        match c.to_ascii_uppercase() {
+            'L' => Some(Turn::Left),
+            'R' => Some(Turn::Right),
+            'U' => Some(Turn::Reverse),
+            _ => None,
+        }
+    }
+}
+
+/// Walker with a position and heading.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Turtle {
+    /// Current position.
+    pub posn: SPoint,
+    /// Current heading.
+    pub heading: Heading,
+}
+
+impl Turtle {
+    /// Make a turtle at the given position and heading.
+    pub fn new(posn: SPoint, heading: Heading) -> Turtle {
+        Turtle { posn, heading }
+    }
+
+    /// Change heading by the given turn.
+    pub fn turn(&mut self, turn: Turn) {
+        self.heading = self.heading.turn(turn);
+    }
+
+    /// Face in the given heading.
+    pub fn face(&mut self, heading: Heading) {
+        self.heading = heading;
+    }
+
+    /// Position one step ahead.
+    pub fn ahead(&self) -> SPoint {
+        self.posn + self.heading.offset()
+    }
+
+    /// Take one step forward.
+    pub fn step(&mut self) {
+        self.posn = self.ahead();
+    }
+
+    /// Take one step forward iff `allowed` accepts the
+    /// position ahead. Returns true iff the step was
+    /// taken.
+    pub fn step_if<F>(&mut self, allowed: F) -> bool
+    where
+        F: FnOnce(SPoint) -> bool,
+    {
+        let next = self.ahead();
+        if allowed(next) {
+            self.posn = next;
+        }
+        self.posn == next
+    }
+
+    /// Take `n` steps forward, calling `visit` with each
+    /// new position. If `visit` returns false, stop there
+    /// and return false. Otherwise return true.
+    pub fn walk<F>(&mut self, n: usize, mut visit: F) -> bool
+    where
+        F: FnMut(SPoint) -> bool,
+    {
+        for _ in 0..n {
+            self.step();
+            if !visit(self.posn) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// Set of visited positions, recording the first
+/// self-intersection.
+#[derive(Clone, Debug, Default)]
+pub struct Trail {
+    /// Positions visited so far.
+    visited: HashSet<SPoint>,
+    /// First position visited twice, if any.
+    crossing: Option<SPoint>,
+}
+
+impl Trail {
+    /// Start a trail at the given position.
+    pub fn new(start: SPoint) -> Trail {
+        let mut trail = Trail::default();
+        trail.visit(start);
+        trail
+    }
+
+    /// Visit the given position. Returns true iff it has
+    /// not been visited before.
+    pub fn visit(&mut self, posn: SPoint) -> bool {
+        let fresh = self.visited.insert(posn);
+        if !fresh && self.crossing.is_none() {
+            self.crossing = Some(posn);
+        }
+        fresh
+    }
+
+    /// Return true iff the given position has been visited.
+    pub fn contains(&self, posn: SPoint) -> bool {
+        self.visited.contains(&posn)
+    }
+
+    /// Number of distinct positions visited.
+    pub fn len(&self) -> usize {
+        self.visited.len()
+    }
+
+    /// Return true iff nothing has been visited.
+    pub fn is_empty(&self) -> bool {
+        self.visited.is_empty()
+    }
+
+    /// The first position visited twice, if any.
+    pub fn first_crossing(&self) -> Option<SPoint> {
+        self.crossing
+    }
+}
+
+/// Rectangular grid of cells, stored in row-major order
+/// and indexed by `Point`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Grid<T> {
+    /// Number of columns.
+    width: usize,
+    /// Number of rows.
+    height: usize,
+    /// Cell contents, row by row.
+    cells: Vec<T>,
+}
+
+impl<T> Grid<T> {
+    /// Create a grid of the given size with every cell
+    /// set to `fill`.
+    pub fn new(width: usize, height: usize, fill: T) -> Self
+    where
+        T: Clone,
+    {
+        Grid {
+            width,
+            height,
+            cells: vec![fill; width * height],
+        }
+    }
+
+    /// Make a grid from lines of text (typically
+    /// `InputLines`), with each character converted to a
+    /// cell by `cell`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the lines are not all the same length.
+    pub fn from_lines<I, F>(lines: I, mut cell: F) -> Self
+    where
+        I: IntoIterator<Item = String>,
+        F: FnMut(char) -> T,
+    {
+        let mut width = None;
+        let mut height = 0;
+        let mut cells = Vec::new();
+        for line in lines {
+            let start = cells.len();
+            cells.extend(line.chars().map(&mut cell));
+            let row_width = cells.len() - start;
+            match width {
+                None => width = Some(row_width),
+                Some(w) => assert!(w == row_width, "grid: ragged row {}", height),
+            }
+            height += 1;
+        }
+        Grid {
+            width: width.unwrap_or(0),
+            height,
+            cells,
+        }
+    }
+
+    /// Number of columns.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Number of rows.
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Clip box for this grid.
+    pub fn grid_box(&self) -> GridBox {
+        GridBox::new(self.width, self.height)
+    }
+
+    /// Return true iff the location is on the grid.
+    pub fn contains(&self, (x, y): Point) -> bool {
+        x < self.width && y < self.height
+    }
+
+    /// Return the cell at the given location, if any.
+    pub fn get(&self, loc: Point) -> Option<&T> {
+        if self.contains(loc) {
+            Some(&self.cells[loc.1 * self.width + loc.0])
+        } else {
+            None
+        }
+    }
+
+    /// Return the cell at the given location mutably, if
+    /// any.
+    pub fn get_mut(&mut self, loc: Point) -> Option<&mut T> {
+        if self.contains(loc) {
+            Some(&mut self.cells[loc.1 * self.width + loc.0])
+        } else {
+            None
+        }
+    }
+
+    /// Return the given row as a slice.
+    pub fn row(&self, y: usize) -> &[T] {
+        assert!(y < self.height);
+        &self.cells[y * self.width..(y + 1) * self.width]
+    }
+
+    /// Return an iterator over the rows, top to bottom.
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        (0..self.height).map(move |y| self.row(y))
+    }
+
+    /// Return an iterator down the given column.
+    pub fn column(&self, x: usize) -> impl Iterator<Item = &T> {
+        assert!(x < self.width);
+        // A grid with no rows has no cells to slice.
+        let cells = self.cells.get(x..).unwrap_or(&[]);
+        cells.iter().step_by(self.width)
+    }
+
+    /// Return an iterator over the columns, left to right.
+    pub fn columns(&self) -> impl Iterator<Item = impl Iterator<Item = &T>> {
+        (0..self.width).map(move |x| self.column(x))
+    }
+
+    /// Return an iterator over the locations of the grid in
+    /// row-major order.
+    pub fn points(&self) -> impl Iterator<Item = Point> {
+        let width = self.width;
+        (0..self.height).flat_map(move |y| (0..width).map(move |x| (x, y)))
+    }
+
+    /// Return an iterator over the locations and cells of
+    /// the grid in row-major order.
+    pub fn iter(&self) -> impl Iterator<Item = (Point, &T)> {
+        self.points().zip(self.cells.iter())
+    }
+
+    /// Return the location of the first cell in row-major
+    /// order satisfying the given predicate, if any.
+    pub fn find<F>(&self, mut pred: F) -> Option<Point>
+    where
+        F: FnMut(&T) -> bool,
+    {
+        self.iter().find(|(_, c)| pred(c)).map(|(p, _)| p)
+    }
+
+    /// Return an iterator over the on-grid neighbors of the
+    /// given location in the given neighborhood.
+    pub fn neighbors(&self, loc: Point, nbhd: Neighborhood) -> Neighbors {
+        self.grid_box().neighbors(loc, nbhd)
+    }
+
+    /// Render the grid as text, one line per row with each
+    /// line terminated by a newline. Each cell is
+    /// formatted by `cell`, which is also given the cell
+    /// location.
+    pub fn render<F, D>(&self, mut cell: F) -> String
+    where
+        F: FnMut(Point, &T) -> D,
+        D: Display,
+    {
+        let mut s = String::new();
+        for (p, c) in self.iter() {
+            write!(s, "{}", cell(p, c)).expect("grid: render failed");
+            if p.0 == self.width - 1 {
+                s.push('\n');
+            }
+        }
+        s
+    }
+}
+
+impl<T> Index<Point> for Grid<T> {
+    type Output = T;
+
+    /// # Panics
+    ///
+    /// Panics if the location is off the grid.
+    fn index(&self, loc: Point) -> &T {
+        self.get(loc).expect("grid: index out of bounds")
+    }
+}
+
+impl<T> IndexMut<Point> for Grid<T> {
+    /// # Panics
+    ///
+    /// Panics if the location is off the grid.
+    fn index_mut(&mut self, loc: Point) -> &mut T {
+        self.get_mut(loc).expect("grid: index out of bounds")
+    }
+}
+
+impl<T: Display> Display for Grid<T> {
+    /// Render the grid using the cell `Display` format.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for ((x, _), c) in self.iter() {
+            write!(f, "{}", c)?;
+            if x == self.width - 1 {
+                writeln!(f)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn moore_clipped_corner() {
+        let clip_box = GridBox::new(3, 3);
+        let mut nbs = clip_box.neighbors((0, 0), Moore).collect::<Vec<_>>();
+        nbs.sort();
+        assert_eq!(nbs, vec![(0, 1), (1, 0), (1, 1)]);
+    }
+
+    #[test]
+    fn signed_unclipped_goes_negative() {
+        let grid_box = GridBox::new_grid();
+        let nbs = grid_box
+            .neighbors((0isize, 0), VonNeumann)
+            .collect::<Vec<_>>();
+        assert_eq!(nbs, vec![(0, -1), (0, 1), (-1, 0), (1, 0)]);
+    }
+
+    #[test]
+    fn hex_neighbors_are_adjacent() {
+        // In axial coordinates, hex distance is half the
+        // Manhattan distance in cube coordinates.
+        let grid_box = GridBox::new_grid();
+        for (q, r) in grid_box.neighbors((2isize, -3), HexAxial) {
+            let (dq, dr) = (q - 2, r + 3);
+            assert_eq!((dq.abs() + dr.abs() + (dq + dr).abs()) / 2, 1);
+        }
+    }
+
+    #[test]
+    fn clip_box_3d() {
+        let clip_box = ClipBox((2usize, 2, 2));
+        assert_eq!(clip_box.neighbors((0, 0, 0), VonNeumann3).count(), 3);
+        assert_eq!(clip_box.neighbors((1, 1, 1), Moore3).count(), 7);
+    }
+
+    #[test]
+    fn spoint_rotation() {
+        let up = SPoint::new(0, -1);
+        assert_eq!(up.turn_left(), SPoint::new(-1, 0));
+        assert_eq!(up.turn_right(), SPoint::new(1, 0));
+        assert_eq!(up.turn_left().turn_left(), -up);
+        assert_eq!(up.turn_right().turn_right().turn_right(), up.turn_left());
+    }
+
+    #[test]
+    fn spoint_distances() {
+        let p = SPoint::new(-2, 5);
+        let q = SPoint::new(3, 1);
+        assert_eq!(p.manhattan_distance(q), 9);
+        assert_eq!(p.chebyshev_distance(q), 5);
+        assert_eq!(chebyshev_distance((0, 5), (3, 1)), 4);
+    }
+
+    #[test]
+    fn spoint_unclipped() {
+        let grid_box = GridBox::new_grid();
+        let nbs = grid_box
+            .neighbors(SPoint::ORIGIN, VonNeumann)
+            .collect::<Vec<_>>();
+        assert_eq!(nbs[0], SPoint::new(0, -1));
+        let clip_box = ClipBox(SPoint::new(2, 2));
+        assert_eq!(clip_box.neighbors(SPoint::ORIGIN, Moore).count(), 3);
+    }
+
+    #[test]
+    fn heading_turns() {
+        for h in Heading::ALL {
+            assert_eq!(h.turn(Turn::Left).offset(), h.offset().turn_left());
+            assert_eq!(h.turn(Turn::Right).offset(), h.offset().turn_right());
+            assert_eq!(h.turn(Turn::Reverse).offset(), -h.offset());
+        }
+    }
+
+    #[test]
+    fn turtle_walk_stops() {
+        let mut turtle = Turtle::new(SPoint::ORIGIN, Heading::from_letter('R').unwrap());
+        let done = turtle.walk(5, |p| p.x < 3);
+        assert!(!done);
+        assert_eq!(turtle.posn, SPoint::new(3, 0));
+        assert!(!turtle.step_if(|p| p.x < 4));
+        assert!(turtle.step_if(|p| p.x == 4));
+        assert_eq!(turtle.posn, SPoint::new(4, 0));
+    }
+
+    #[test]
+    fn grid_columns() {
+        let grid = Grid::from_lines(["abc".to_string(), "def".to_string()], |c| c);
+        let columns: Vec<String> = grid.columns().map(|c| c.collect()).collect();
+        assert_eq!(columns, ["ad", "be", "cf"]);
+
+        let empty = Grid::new(3, 0, '.');
+        assert_eq!(empty.column(2).count(), 0);
+        assert_eq!(empty.columns().count(), 3);
+    }
+}
@@ -0,0 +1,802 @@
+// Copyright © 2016 Bart Massey
+// This program is licensed under the "MIT License".
+// Please see the file COPYING in this distribution
+// for license terms.
+
+//! Searching MD5 hashes for interesting hex digits.
+//!
+//! Several puzzles hash a fixed prefix followed by some
+//! varying suffix — usually a decimal index — and then look
+//! at the hex digits (nybbles) of the result. A
+//! `SeededHasher` hashes the prefix once and reuses that
+//! work for every suffix. The hash is MD5 by default, but
+//! can be any `PuzzleHasher`. A `NybbleView` looks at a
+//! digest as a sequence of nybbles, most significant first,
+//! and answers the usual questions about it; the predicate
+//! constructors `starts_with_zeros()`, `has_run()` and
+//! `all_at_least()` wrap these up as closures.
+//!
+//! `scan_indices()` searches a range of indices for
+//! interesting hashes, splitting the work into chunks
+//! hashed on several threads. Results come back in index
+//! order regardless, so a search that stops at the first
+//! few hits gets the same answer as a sequential one. The
+//! scan can optionally record its progress in a file, so
+//! that a later scan with the same prefix and filter can
+//! skip straight to the hits. `mine_zeros()` is the common
+//! case: digests starting with some number of zero
+//! nybbles.
+//!
+//! A `HashWindow` is for searches that look ahead from
+//! each index: it remembers values computed for a sliding
+//! window of indices, while worker threads compute the
+//! values just past the window in the background.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use aoc::{NybbleView, SeededHasher};
+//!
+//! let hasher = SeededHasher::new(b"abc");
+//! let digest = hasher.hash_index(3231929);
+//! let view = NybbleView::new(&digest);
+//! assert_eq!(view.leading_zeros(), 5);
+//! assert_eq!(view.get(5), 1);
+//!
+//! let zeros5 = aoc::starts_with_zeros(5);
+//! let hits: Vec<(usize, u8)> = aoc::scan_indices(&hasher, 3231000..3233000, |_, digest| {
+//!     let view = NybbleView::new(digest);
+//!     if zeros5(&view) {
+//!         Some(view.get(5))
+//!     } else {
+//!         None
+//!     }
+//! })
+//! .collect();
+//! assert_eq!(hits, [(3231929, 1)]);
+//! ```
+
+use std::collections::{BTreeMap, VecDeque};
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc, Condvar, Mutex};
+use std::thread;
+
+use crate::bitset::BitSet64;
+use crate::hex::{has_repeat, nybbles, Nybbles};
+use crate::puzzlehash::{Md5, PuzzleHasher};
+
+/// An MD5 digest.
+pub type Md5Digest = [u8; 16];
+
+/// Write the decimal representation of `n` into the end of
+/// `buf`, returning the digits.
+pub(crate) fn decimal(mut n: usize, buf: &mut [u8; 20]) -> &[u8] {
+    let mut start = buf.len();
+    loop {
+        start -= 1;
+        buf[start] = b'0' + (n % 10) as u8;
+        n /= 10;
+        if n == 0 {
+            break;
+        }
+    }
+    &buf[start..]
+}
+
+/// Hasher that has already hashed a fixed prefix. The hash
+/// function is MD5 unless otherwise specified.
+#[derive(Clone)]
+pub struct SeededHasher<H = Md5> {
+    /// The prefix.
+    prefix: Vec<u8>,
+    /// Hasher state after the prefix.
+    seeded: H,
+}
+
+impl SeededHasher {
+    /// MD5 hasher for messages starting with `prefix`.
+    pub fn new(prefix: &[u8]) -> Self {
+        Self::with_prefix(prefix)
+    }
+}
+
+impl<H: PuzzleHasher> SeededHasher<H> {
+    /// Hasher for messages starting with `prefix`.
+    pub fn with_prefix(prefix: &[u8]) -> Self {
+        let mut seeded = H::new();
+        seeded.update(prefix);
+        SeededHasher {
+            prefix: prefix.to_vec(),
+            seeded,
+        }
+    }
+
+    /// Hasher for messages starting with this hasher's
+    /// prefix followed by `bytes`.
+    pub fn extend(&self, bytes: &[u8]) -> Self {
+        let mut prefix = self.prefix.clone();
+        prefix.extend_from_slice(bytes);
+        let mut seeded = self.seeded.clone();
+        seeded.update(bytes);
+        SeededHasher { prefix, seeded }
+    }
+
+    /// Digest of the prefix followed by `suffix`.
+    pub fn hash(&self, suffix: &[u8]) -> H::Output {
+        let mut hasher = self.seeded.clone();
+        hasher.update(suffix);
+        hasher.finalize()
+    }
+
+    /// Digest of the prefix followed by the decimal digits
+    /// of `index`.
+    pub fn hash_index(&self, index: usize) -> H::Output {
+        let mut buf = [0; 20];
+        self.hash(decimal(index, &mut buf))
+    }
+
+    /// Call `f` with each index in `indices` and the digest
+    /// of the prefix followed by its decimal digits, in
+    /// index order. MD5 hashes several indices at once.
+    pub fn hash_indices<F>(&self, indices: Range<usize>, f: F)
+    where
+        F: FnMut(usize, &H::Output),
+    {
+        H::hash_indices(&self.seeded, &self.prefix, indices, f);
+    }
+}
+
+/// View of a digest as a sequence of nybbles (hex digits),
+/// most significant first.
+#[derive(Clone, Copy, Debug)]
+pub struct NybbleView<'a>(&'a [u8]);
+
+impl<'a> NybbleView<'a> {
+    /// View the given digest bytes.
+    pub fn new(bytes: &'a [u8]) -> Self {
+        NybbleView(bytes)
+    }
+
+    /// Number of nybbles.
+    pub fn len(&self) -> usize {
+        2 * self.0.len()
+    }
+
+    /// True if there are no nybbles.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Nybble `i`.
+    pub fn get(&self, i: usize) -> u8 {
+        let b = self.0[i >> 1];
+        if i & 1 == 0 {
+            b >> 4
+        } else {
+            b & 0xf
+        }
+    }
+
+    /// Iterator over the nybbles.
+    pub fn iter(&self) -> Nybbles<'a> {
+        nybbles(self.0)
+    }
+
+    /// Number of leading zero nybbles.
+    pub fn leading_zeros(&self) -> usize {
+        self.iter().take_while(|&d| d == 0).count()
+    }
+
+    /// The digit of the first run of `k` identical
+    /// consecutive nybbles, if any.
+    pub fn first_run(&self, k: usize) -> Option<u8> {
+        has_repeat(self.0, None, k)
+    }
+
+    /// True if there is a run of `k` consecutive nybbles
+    /// equal to `digit`.
+    pub fn has_run_of(&self, digit: u8, k: usize) -> bool {
+        k == 0 || has_repeat(self.0, Some(digit), k).is_some()
+    }
+
+    /// Positions among the first `m` nybbles whose value is
+    /// at least `threshold`.
+    pub fn first_at_least(&self, m: usize, threshold: u8) -> BitSet64 {
+        assert!(m <= BitSet64::CAPACITY && m <= self.len());
+        (0..m).filter(|&i| self.get(i) >= threshold).collect()
+    }
+}
+
+/// Predicate: the digest starts with at least `n` zero
+/// nybbles.
+pub fn starts_with_zeros(n: usize) -> impl Fn(&NybbleView) -> bool {
+    move |view| view.leading_zeros() >= n
+}
+
+/// Predicate: the digest has a run of `k` identical
+/// consecutive nybbles.
+pub fn has_run(k: usize) -> impl Fn(&NybbleView) -> bool {
+    move |view| view.first_run(k).is_some()
+}
+
+/// Predicate: the first `m` nybbles of the digest are all
+/// at least `threshold`.
+pub fn all_at_least(m: usize, threshold: u8) -> impl Fn(&NybbleView) -> bool {
+    move |view| (0..m).all(|i| view.get(i) >= threshold)
+}
+
+/// Record of the progress of a scan from index 0, kept in
+/// a file as lines `hit <index>` and `scanned <index>`.
+/// Hits are only trusted up to the last `scanned` mark, so
+/// an interrupted write loses nothing that matters.
+struct ScanCache {
+    /// Path of the cache file.
+    path: PathBuf,
+    /// Indices below this have all been scanned.
+    scanned: usize,
+    /// Indices of hits below `scanned`, in order.
+    hits: VecDeque<usize>,
+}
+
+impl ScanCache {
+    /// Load the cache from the given file, or start an
+    /// empty one if the file cannot be read.
+    fn load(path: &Path) -> Self {
+        let mut cache = ScanCache {
+            path: path.to_path_buf(),
+            scanned: 0,
+            hits: VecDeque::new(),
+        };
+        let file = match File::open(path) {
+            Ok(file) => file,
+            Err(_) => return cache,
+        };
+        let mut pending = Vec::new();
+        for line in BufReader::new(file).lines() {
+            let line = match line {
+                Ok(line) => line,
+                Err(_) => break,
+            };
+            let mut words = line.split_whitespace();
+            let (tag, index) = match (words.next(), words.next().map(str::parse)) {
+                (Some(tag), Some(Ok(index))) => (tag, index),
+                _ => break,
+            };
+            match tag {
+                "hit" => pending.push(index),
+                "scanned" => {
+                    cache.hits.extend(pending.drain(..));
+                    cache.scanned = index;
+                }
+                _ => break,
+            }
+        }
+        cache
+    }
+
+    /// Append the hits of a newly scanned range ending at
+    /// `scanned` to the cache file.
+    fn record(&mut self, hits: &[usize], scanned: usize) -> std::io::Result<()> {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        let mut text = String::new();
+        for index in hits {
+            text += &format!("hit {}\n", index);
+        }
+        text += &format!("scanned {}\n", scanned);
+        file.write_all(text.as_bytes())?;
+        self.scanned = scanned;
+        Ok(())
+    }
+}
+
+/// Iterator over the interesting hashes in a range of
+/// indices, in index order. See `scan_indices()`.
+pub struct IndexScan<R, F, H = Md5> {
+    /// Hasher for the indices.
+    hasher: SeededHasher<H>,
+    /// Extracts results from interesting digests.
+    filter: F,
+    /// Indices remaining to be scanned.
+    indices: Range<usize>,
+    /// Number of worker threads.
+    nthreads: usize,
+    /// Number of indices per worker per batch.
+    chunk_size: usize,
+    /// Results scanned but not yet returned.
+    pending: VecDeque<(usize, R)>,
+    /// Record of previous scans, if any.
+    cache: Option<ScanCache>,
+}
+
+/// Search the hashes of `indices` with the given
+/// `hasher`. The `filter` is called with each index and
+/// its digest, and returns a result for the interesting
+/// ones. The returned iterator yields `(index, result)`
+/// pairs in index order.
+///
+/// By default the scan uses all available CPUs; see
+/// `IndexScan::threads()`, `IndexScan::chunk_size()` and
+/// `IndexScan::cache()` for tuning.
+pub fn scan_indices<R, F, H>(
+    hasher: &SeededHasher<H>,
+    indices: Range<usize>,
+    filter: F,
+) -> IndexScan<R, F, H>
+where
+    R: Send,
+    F: Fn(usize, &H::Output) -> Option<R> + Sync,
+    H: PuzzleHasher,
+{
+    let nthreads = thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1);
+    IndexScan {
+        hasher: hasher.clone(),
+        filter,
+        indices,
+        nthreads,
+        chunk_size: 4096,
+        pending: VecDeque::new(),
+        cache: None,
+    }
+}
+
+/// Mine for digests starting with at least `nzeros` zero
+/// nybbles, trying indices from 0 up, in the style of the
+/// "AdventCoin" puzzles. The returned iterator yields
+/// `(index, digest)` pairs in index order, however many
+/// worker threads do the hashing.
+#[allow(clippy::type_complexity)]
+pub fn mine_zeros<H: PuzzleHasher>(
+    hasher: &SeededHasher<H>,
+    nzeros: usize,
+) -> IndexScan<H::Output, impl Fn(usize, &H::Output) -> Option<H::Output> + Sync, H> {
+    let interesting = starts_with_zeros(nzeros);
+    let filter = move |_: usize, digest: &H::Output| {
+        if interesting(&NybbleView::new(digest.as_ref())) {
+            Some(*digest)
+        } else {
+            None
+        }
+    };
+    // Hits are rare, so hand out big chunks.
+    scan_indices(hasher, 0..usize::MAX, filter).chunk_size(1 << 14)
+}
+
+impl<R, F, H> IndexScan<R, F, H>
+where
+    R: Send,
+    F: Fn(usize, &H::Output) -> Option<R> + Sync,
+    H: PuzzleHasher,
+{
+    /// Use `nthreads` worker threads.
+    pub fn threads(mut self, nthreads: usize) -> Self {
+        assert!(nthreads > 0, "no threads");
+        self.nthreads = nthreads;
+        self
+    }
+
+    /// Give each worker `chunk_size` consecutive indices
+    /// at a time.
+    pub fn chunk_size(mut self, chunk_size: usize) -> Self {
+        assert!(chunk_size > 0, "empty chunks");
+        self.chunk_size = chunk_size;
+        self
+    }
+
+    /// Keep a record of the scan in the file at `path`,
+    /// and use any existing record there to skip work. The
+    /// record covers indices from 0 up to where some scan
+    /// left off, and is only meaningful for the same hasher
+    /// prefix and filter: it is up to the caller to choose a
+    /// path that identifies them. If the record cannot be
+    /// written, the scan carries on without it.
+    pub fn cache<P: AsRef<Path>>(mut self, path: P) -> Self {
+        self.cache = Some(ScanCache::load(path.as_ref()));
+        self
+    }
+
+    /// Hash a range of indices, splitting it among the
+    /// worker threads, and return the hits in order.
+    fn scan_range(&self, range: Range<usize>) -> Vec<(usize, R)> {
+        let hasher = &self.hasher;
+        let filter = &self.filter;
+        let scan_chunk = move |chunk: Range<usize>| {
+            let mut hits = Vec::new();
+            hasher.hash_indices(chunk, |i, digest| {
+                if let Some(r) = filter(i, digest) {
+                    hits.push((i, r));
+                }
+            });
+            hits
+        };
+        let chunks: Vec<Range<usize>> = range
+            .clone()
+            .step_by(self.chunk_size)
+            .map(|start| start..range.end.min(start.saturating_add(self.chunk_size)))
+            .collect();
+        if chunks.len() <= 1 {
+            return chunks.into_iter().flat_map(scan_chunk).collect();
+        }
+        thread::scope(|s| {
+            let workers: Vec<_> = chunks
+                .into_iter()
+                .map(|chunk| s.spawn(move || scan_chunk(chunk)))
+                .collect();
+            workers
+                .into_iter()
+                .flat_map(|w| w.join().expect("scan worker panicked"))
+                .collect()
+        })
+    }
+
+    /// Scan the next batch of indices into `pending`.
+    fn refill(&mut self) {
+        let start = self.indices.start;
+
+        // Replay cached hits if possible.
+        if let Some(cache) = self.cache.as_mut() {
+            if start < cache.scanned {
+                let end = cache.scanned.min(self.indices.end);
+                while let Some(&i) = cache.hits.front() {
+                    if i >= end {
+                        break;
+                    }
+                    cache.hits.pop_front();
+                    if i >= start {
+                        if let Some(r) = (self.filter)(i, &self.hasher.hash_index(i)) {
+                            self.pending.push_back((i, r));
+                        }
+                    }
+                }
+                self.indices.start = end;
+                return;
+            }
+        }
+
+        let batch = self.nthreads.saturating_mul(self.chunk_size);
+        let end = self.indices.end.min(start.saturating_add(batch));
+        let hits = self.scan_range(start..end);
+        // Only extend the record if it stays contiguous.
+        if let Some(cache) = self.cache.as_mut().filter(|c| c.scanned == start) {
+            let indices: Vec<usize> = hits.iter().map(|&(i, _)| i).collect();
+            if cache.record(&indices, end).is_err() {
+                self.cache = None;
+            }
+        }
+        self.pending.extend(hits);
+        self.indices.start = end;
+    }
+}
+
+impl<R, F, H> Iterator for IndexScan<R, F, H>
+where
+    R: Send,
+    F: Fn(usize, &H::Output) -> Option<R> + Sync,
+    H: PuzzleHasher,
+{
+    type Item = (usize, R);
+
+    fn next(&mut self) -> Option<(usize, R)> {
+        while self.pending.is_empty() && !self.indices.is_empty() {
+            self.refill();
+        }
+        self.pending.pop_front()
+    }
+}
+
+/// Number of indices each `HashWindow` worker computes at a
+/// time.
+const WINDOW_CHUNK: usize = 16;
+
+/// State shared between a `HashWindow` and its workers.
+struct WindowLimit {
+    /// Workers may start blocks below this index.
+    limit: usize,
+    /// Workers should quit.
+    stop: bool,
+}
+
+/// Sliding-window cache of values computed for increasing
+/// indices, such as expensive hashes. Worker threads
+/// compute values ahead of the window, up to a given
+/// lookahead past its start. Values before the start of the
+/// window are forgotten as it advances.
+pub struct HashWindow<T> {
+    /// The window starts here.
+    base: usize,
+    /// Index of the first value in `window`.
+    start: usize,
+    /// Computed values, in index order.
+    window: VecDeque<T>,
+    /// Computed blocks that arrived out of order, by
+    /// starting index.
+    early: BTreeMap<usize, Vec<T>>,
+    /// How far past `base` workers should compute.
+    lookahead: usize,
+    /// Computed blocks from the workers.
+    blocks: mpsc::Receiver<(usize, Vec<T>)>,
+    /// Limit on worker progress, with its wakeup.
+    limit: Arc<(Mutex<WindowLimit>, Condvar)>,
+    /// Worker threads.
+    workers: Vec<thread::JoinHandle<()>>,
+}
+
+impl<T: Send + 'static> HashWindow<T> {
+    /// Window of values `f(i)` for `i` from 0 up, computed
+    /// up to `lookahead` past the window start on all
+    /// available CPUs.
+    pub fn new<F>(lookahead: usize, f: F) -> Self
+    where
+        F: Fn(usize) -> T + Send + Sync + 'static,
+    {
+        let nthreads = thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(1);
+        Self::with_threads(lookahead, nthreads, f)
+    }
+
+    /// Window of values `f(i)` for `i` from 0 up, computed
+    /// up to `lookahead` past the window start by `nthreads`
+    /// worker threads.
+    pub fn with_threads<F>(lookahead: usize, nthreads: usize, f: F) -> Self
+    where
+        F: Fn(usize) -> T + Send + Sync + 'static,
+    {
+        assert!(nthreads > 0, "no threads");
+        let f = Arc::new(f);
+        let next = Arc::new(AtomicUsize::new(0));
+        let limit = Arc::new((
+            Mutex::new(WindowLimit {
+                limit: lookahead,
+                stop: false,
+            }),
+            Condvar::new(),
+        ));
+        let (send, blocks) = mpsc::channel();
+        let workers = (0..nthreads)
+            .map(|_| {
+                let f = Arc::clone(&f);
+                let next = Arc::clone(&next);
+                let limit = Arc::clone(&limit);
+                let send = send.clone();
+                thread::spawn(move || loop {
+                    // Claim a block, then wait until it is
+                    // wanted.
+                    let start = next.fetch_add(WINDOW_CHUNK, Ordering::Relaxed);
+                    let (lock, wakeup) = &*limit;
+                    let mut state = lock.lock().expect("window lock poisoned");
+                    while !state.stop && start >= state.limit {
+                        state = wakeup.wait(state).expect("window lock poisoned");
+                    }
+                    if state.stop {
+                        return;
+                    }
+                    drop(state);
+                    let values = (start..start + WINDOW_CHUNK).map(|i| f(i)).collect();
+                    if send.send((start, values)).is_err() {
+                        return;
+                    }
+                })
+            })
+            .collect();
+        HashWindow {
+            base: 0,
+            start: 0,
+            window: VecDeque::new(),
+            early: BTreeMap::new(),
+            lookahead,
+            blocks,
+            limit,
+            workers,
+        }
+    }
+
+    /// Let the workers compute up to `limit`.
+    fn raise_limit(&self, limit: usize) {
+        let (lock, wakeup) = &*self.limit;
+        let mut state = lock.lock().expect("window lock poisoned");
+        if limit > state.limit {
+            state.limit = limit;
+            wakeup.notify_all();
+        }
+    }
+
+    /// Move the start of the window forward to `base`,
+    /// forgetting the values before it.
+    pub fn advance(&mut self, base: usize) {
+        assert!(base >= self.base, "HashWindow cannot move back");
+        self.base = base;
+        while self.start < self.base && self.window.pop_front().is_some() {
+            self.start += 1;
+        }
+        self.raise_limit(base.saturating_add(self.lookahead));
+    }
+
+    /// Value for index `i`, which must be in the window.
+    /// Waits for the workers if necessary.
+    pub fn get(&mut self, i: usize) -> &T {
+        assert!(i >= self.base, "HashWindow index before window");
+        while i >= self.start + self.window.len() {
+            self.raise_limit(i + 1);
+            let (block_start, values) = self.blocks.recv().expect("window workers died");
+            self.early.insert(block_start, values);
+            while let Some(values) = self.early.remove(&(self.start + self.window.len())) {
+                self.window.extend(values);
+            }
+            while self.start < self.base && self.window.pop_front().is_some() {
+                self.start += 1;
+            }
+        }
+        &self.window[i - self.start]
+    }
+}
+
+impl<T> Drop for HashWindow<T> {
+    fn drop(&mut self) {
+        let (lock, wakeup) = &*self.limit;
+        if let Ok(mut state) = lock.lock() {
+            state.stop = true;
+            wakeup.notify_all();
+        }
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::puzzlehash::FastHash;
+    use crate::sets::Set;
+
+    #[test]
+    fn test_decimal() {
+        let mut buf = [0; 20];
+        for n in [0, 7, 10, 12345, usize::MAX] {
+            assert_eq!(decimal(n, &mut buf), n.to_string().as_bytes());
+        }
+    }
+
+    #[test]
+    fn test_hash_indices() {
+        let md5 = SeededHasher::new(b"ab").extend(b"c");
+        let fast = SeededHasher::<FastHash>::with_prefix(b"abc");
+        let mut seen = Vec::new();
+        md5.hash_indices(3231925..3231936, |i, digest| {
+            assert_eq!(*digest, Md5::digest(format!("abc{}", i).as_bytes()));
+            seen.push(i);
+        });
+        fast.hash_indices(3231925..3231936, |i, digest| {
+            assert_eq!(*digest, FastHash::digest(format!("abc{}", i).as_bytes()));
+            seen.push(i);
+        });
+        let expected: Vec<usize> = (3231925..3231936).chain(3231925..3231936).collect();
+        assert_eq!(seen, expected);
+    }
+
+    #[test]
+    fn test_nybble_view() {
+        let bytes = [0x00, 0x0f, 0xff, 0xf1, 0x23];
+        let view = NybbleView::new(&bytes);
+        assert_eq!(view.len(), 10);
+        let nybbles: Vec<u8> = view.iter().collect();
+        assert_eq!(nybbles, [0, 0, 0, 0xf, 0xf, 0xf, 0xf, 1, 2, 3]);
+        assert_eq!(view.leading_zeros(), 3);
+        assert_eq!(view.first_run(3), Some(0));
+        assert_eq!(view.first_run(4), Some(0xf));
+        assert_eq!(view.first_run(5), None);
+        assert!(view.has_run_of(0xf, 4));
+        assert!(!view.has_run_of(0, 4));
+        assert!(starts_with_zeros(3)(&view));
+        assert!(!starts_with_zeros(4)(&view));
+        assert!(has_run(4)(&view));
+        let high: Vec<usize> = Set::elems(&view.first_at_least(8, 2)).collect();
+        assert_eq!(high, [3, 4, 5, 6]);
+        assert!(all_at_least(0, 0xf)(&view));
+        assert!(!all_at_least(1, 1)(&view));
+    }
+
+    #[test]
+    fn test_scan_matches_sequential() {
+        let hasher = SeededHasher::new(b"abc");
+        let filter = |i: usize, digest: &Md5Digest| {
+            let view = NybbleView::new(digest);
+            view.first_run(3).map(|d| (i, d))
+        };
+        let expected: Vec<_> = (0..3000)
+            .filter_map(|i| filter(i, &hasher.hash_index(i)).map(|r| (i, r)))
+            .collect();
+        for (nthreads, chunk_size) in [(1, 5000), (3, 7), (4, 100)] {
+            let found: Vec<_> = scan_indices(&hasher, 0..3000, filter)
+                .threads(nthreads)
+                .chunk_size(chunk_size)
+                .collect();
+            assert_eq!(found, expected);
+        }
+        assert_eq!(expected[0].0, 18);
+    }
+
+    #[test]
+    fn test_mine_zeros() {
+        let hasher = SeededHasher::new(b"abc");
+        let expected: Vec<usize> = (0..20000)
+            .filter(|&i| NybbleView::new(&hasher.hash_index(i)).leading_zeros() >= 3)
+            .collect();
+        assert!(expected.len() > 2);
+        for nthreads in [1, 2, 5] {
+            let found: Vec<usize> = mine_zeros(&hasher, 3)
+                .threads(nthreads)
+                .chunk_size(1000)
+                .map(|(i, _)| i)
+                .take_while(|&i| i < 20000)
+                .collect();
+            assert_eq!(found, expected);
+        }
+    }
+
+    #[test]
+    fn test_hash_window() {
+        for nthreads in [1, 3] {
+            let mut window = HashWindow::with_threads(50, nthreads, |i| i * i);
+            for base in [0, 1, 17, 100, 101, 1000] {
+                window.advance(base);
+                for i in [base + 40, base, base + 3, base + 200] {
+                    assert_eq!(*window.get(i), i * i);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_scan_cache() {
+        let path = std::env::temp_dir().join(format!("aoc-scan-cache-{}", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let hasher = SeededHasher::new(b"abc");
+        let filter = |_: usize, digest: &Md5Digest| NybbleView::new(digest).first_run(3);
+        let scan = |range: Range<usize>| {
+            scan_indices(&hasher, range, filter)
+                .threads(2)
+                .chunk_size(100)
+                .cache(&path)
+                .collect::<Vec<_>>()
+        };
+        let first = scan(0..1000);
+        assert_eq!(ScanCache::load(&path).scanned, 1000);
+        let again = scan(0..1000);
+        assert_eq!(again, first);
+        let longer = scan(500..2000);
+        let expected: Vec<_> = scan_indices(&hasher, 500..2000, filter).collect();
+        assert_eq!(longer, expected);
+        let cache = ScanCache::load(&path);
+        assert_eq!(cache.scanned, 2000);
+        assert_eq!(cache.hits.len(), scan(0..2000).len());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_scan_cache_unwritable() {
+        let path = std::env::temp_dir()
+            .join(format!("aoc-no-such-dir-{}", std::process::id()))
+            .join("scan-cache");
+        let hasher = SeededHasher::new(b"abc");
+        let filter = |_: usize, digest: &Md5Digest| NybbleView::new(digest).first_run(3);
+        let mut scan = scan_indices(&hasher, 0..1000, filter)
+            .chunk_size(100)
+            .cache(&path);
+        let first = scan.next();
+        assert!(scan.cache.is_none());
+        let found: Vec<_> = first.into_iter().chain(scan).collect();
+        let expected: Vec<_> = scan_indices(&hasher, 0..1000, filter).collect();
+        assert_eq!(found, expected);
+        assert!(!path.exists());
+    }
+}
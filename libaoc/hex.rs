@@ -6,28 +6,239 @@
 //! Hexadecimal utilities for Advent of Code 2016 solutions.
 //!
 //! These are not strictly necessary, but provide some
-//! convenience.
+//! convenience. `hex_encode_into()` writes hex digits into
+//! a caller's buffer through a lookup table, for inner
+//! loops that hash hex strings over and over.
+//!
+//! # Examples
+//!
+//! ```rust
+//! let mut buf = [0; 4];
+//! aoc::hex_encode_into(&[0xbe, 0xef], &mut buf);
+//! assert_eq!(&buf, b"beef");
+//! assert_eq!(aoc::hex_decode("BEef"), Ok(vec![0xbe, 0xef]));
+//! assert!(aoc::hex_decode("bee").is_err());
+//!
+//! let nybbles: Vec<u8> = aoc::nybbles(&[0x12, 0x22, 0x2f]).collect();
+//! assert_eq!(nybbles, [1, 2, 2, 2, 2, 0xf]);
+//! assert_eq!(aoc::has_repeat(&[0x12, 0x22, 0x2f], None, 3), Some(2));
+//! ```
+
+use std::fmt;
 
 /// Given an input between 0 and 15, return the
 /// corresponding hex digit. This may be faster than
 /// `std::char::from_digit()`.
 #[inline]
 pub fn hex_digit(n: u8) -> char {
-    let d = if n <= 9 {
-        n as u8 + b'0'
-    } else {
-        n as u8 - 10 + b'a'
-    };
+    let d = if n <= 9 { n + b'0' } else { n - 10 + b'a' };
     d as char
 }
 
+/// The two hex digits of each byte value.
+static HEX_PAIRS: [[u8; 2]; 256] = {
+    let digits = b"0123456789abcdef";
+    let mut table = [[0; 2]; 256];
+    let mut b = 0;
+    while b < 256 {
+        table[b] = [digits[b >> 4], digits[b & 0xf]];
+        b += 1;
+    }
+    table
+};
+
+/// Write the lowercase hex digits of `bytes` into `out`,
+/// which must be exactly twice as long.
+#[inline]
+pub fn hex_encode_into(bytes: &[u8], out: &mut [u8]) {
+    assert_eq!(out.len(), 2 * bytes.len(), "hex buffer size mismatch");
+    for (&b, pair) in bytes.iter().zip(out.chunks_exact_mut(2)) {
+        pair.copy_from_slice(&HEX_PAIRS[b as usize]);
+    }
+}
+
 /// Convert a byte vector to a hexadecimal string.
 #[inline]
 pub fn hex_string(bytes: &[u8]) -> String {
-    let mut s = String::new();
-    for b in bytes {
-        s.push(hex_digit(b >> 4));
-        s.push(hex_digit(b & 0xf));
+    let mut out = vec![0; 2 * bytes.len()];
+    hex_encode_into(bytes, &mut out);
+    String::from_utf8(out).expect("hex digits not UTF-8")
+}
+
+/// Error from decoding a hex string.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HexError {
+    /// The string has an odd number of digits.
+    OddLength,
+    /// The character at the given position is not a hex
+    /// digit.
+    BadDigit(usize, char),
+}
+
+impl fmt::Display for HexError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            HexError::OddLength => write!(f, "odd number of hex digits"),
+            HexError::BadDigit(posn, c) => {
+                write!(f, "bad hex digit {:?} at position {}", c, posn)
+            }
+        }
+    }
+}
+
+impl std::error::Error for HexError {}
+
+/// Value of the hex digit `c` (either case), if it is one.
+#[inline]
+pub fn hex_value(c: u8) -> Option<u8> {
+    match c {
+        b'0'..=b'9' => Some(c - b'0'),
+        b'a'..=b'f' => Some(c - b'a' + 10),
+        b'A'..=b'F' => Some(c - b'A' + 10),
+        _ => None,
+    }
+}
+
+/// Decode the hex digits `hex` into `out`, which must be
+/// exactly half as long.
+pub fn hex_decode_into(hex: &[u8], out: &mut [u8]) -> Result<(), HexError> {
+    if !hex.len().is_multiple_of(2) {
+        return Err(HexError::OddLength);
+    }
+    assert_eq!(2 * out.len(), hex.len(), "hex buffer size mismatch");
+    let digit =
+        |posn: usize| hex_value(hex[posn]).ok_or(HexError::BadDigit(posn, hex[posn] as char));
+    for (i, b) in out.iter_mut().enumerate() {
+        *b = (digit(2 * i)? << 4) | digit(2 * i + 1)?;
+    }
+    Ok(())
+}
+
+/// Decode a string of hex digits into bytes.
+pub fn hex_decode(hex: &str) -> Result<Vec<u8>, HexError> {
+    // Report non-ASCII characters whole, by character
+    // position.
+    if let Some((posn, c)) = hex.chars().enumerate().find(|(_, c)| !c.is_ascii()) {
+        return Err(HexError::BadDigit(posn, c));
+    }
+    let mut out = vec![0; hex.len() / 2];
+    hex_decode_into(hex.as_bytes(), &mut out)?;
+    Ok(out)
+}
+
+/// Iterator over the nybbles (hex digit values) of some
+/// bytes, most significant first.
+#[derive(Clone, Debug)]
+pub struct Nybbles<'a> {
+    /// The bytes.
+    bytes: &'a [u8],
+    /// Position of the next nybble.
+    posn: usize,
+}
+
+/// Iterate over the nybbles of `bytes`, most significant
+/// first.
+pub fn nybbles(bytes: &[u8]) -> Nybbles<'_> {
+    Nybbles { bytes, posn: 0 }
+}
+
+impl Iterator for Nybbles<'_> {
+    type Item = u8;
+
+    fn next(&mut self) -> Option<u8> {
+        let b = *self.bytes.get(self.posn >> 1)?;
+        let d = if self.posn & 1 == 0 { b >> 4 } else { b & 0xf };
+        self.posn += 1;
+        Some(d)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let n = 2 * self.bytes.len() - self.posn;
+        (n, Some(n))
+    }
+}
+
+impl ExactSizeIterator for Nybbles<'_> {}
+
+/// Look for `reps` consecutive repetitions of a `target`
+/// nybble in `bytes`, returning the repeated nybble. If
+/// `target` is `None`, the first run of any nybble counts.
+pub fn has_repeat(bytes: &[u8], target: Option<u8>, reps: usize) -> Option<u8> {
+    assert!(reps > 0, "empty run");
+    let mut run = 0;
+    let mut prev = None;
+    for d in nybbles(bytes) {
+        if target.is_some_and(|t| t != d) {
+            run = 0;
+            continue;
+        }
+        if prev == Some(d) && run > 0 {
+            run += 1;
+        } else {
+            run = 1;
+            prev = Some(d);
+        }
+        if run == reps {
+            return prev;
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_all_bytes() {
+        let bytes: Vec<u8> = (0..=255).collect();
+        let mut out = vec![0; 512];
+        hex_encode_into(&bytes, &mut out);
+        let expected: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+        assert_eq!(out, expected.as_bytes());
+        assert_eq!(hex_string(&bytes), expected);
+        assert_eq!(hex_decode(&expected), Ok(bytes.clone()));
+        assert_eq!(hex_decode(&expected.to_uppercase()), Ok(bytes.clone()));
+        for b in 0..=255u8 {
+            let pair = &out[2 * b as usize..2 * b as usize + 2];
+            let mut decoded = [0];
+            hex_decode_into(pair, &mut decoded).unwrap();
+            assert_eq!(decoded[0], b);
+            let digits: Vec<char> = nybbles(&[b]).map(hex_digit).collect();
+            assert_eq!(digits, [pair[0] as char, pair[1] as char]);
+        }
+    }
+
+    #[test]
+    fn test_hex_value() {
+        for c in 0..=255u8 {
+            let expected = (c as char).to_digit(16).map(|d| d as u8);
+            assert_eq!(hex_value(c), expected, "{}", c);
+        }
+    }
+
+    #[test]
+    fn test_decode_errors() {
+        assert_eq!(hex_decode("abc"), Err(HexError::OddLength));
+        assert_eq!(hex_decode("0g"), Err(HexError::BadDigit(1, 'g')));
+        assert_eq!(hex_decode("00é0"), Err(HexError::BadDigit(2, 'é')));
+        assert_eq!(
+            HexError::BadDigit(3, 'x').to_string(),
+            "bad hex digit 'x' at position 3"
+        );
+        assert_eq!(hex_decode(""), Ok(Vec::new()));
+    }
+
+    #[test]
+    fn test_has_repeat() {
+        let bytes = [0x11, 0x12, 0x22, 0x2f, 0xff];
+        assert_eq!(has_repeat(&bytes, None, 3), Some(1));
+        assert_eq!(has_repeat(&bytes, None, 4), Some(2));
+        assert_eq!(has_repeat(&bytes, Some(2), 4), Some(2));
+        assert_eq!(has_repeat(&bytes, Some(0xf), 3), Some(0xf));
+        assert_eq!(has_repeat(&bytes, Some(0xf), 4), None);
+        assert_eq!(has_repeat(&bytes, None, 5), None);
+        assert_eq!(has_repeat(&bytes, Some(3), 1), None);
+        assert_eq!(nybbles(&bytes).len(), 10);
     }
-    s
 }
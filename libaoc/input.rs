@@ -0,0 +1,228 @@
+// Copyright © 2016 Bart Massey
+// This program is licensed under the "MIT License".
+// Please see the file COPYING in this distribution
+// for license terms.
+
+//! Whole-input and byte-at-a-time readers for Advent of
+//! Code 2016 solutions.
+//!
+//! Some puzzles have a single "line" of input, for which
+//! `InputLines` is the wrong tool. `input_string()` and
+//! `input_bytes()` read all of `stdin`, panicking on
+//! failure. `ByteReader` instead streams its input a byte
+//! at a time, with a byte of lookahead, so that input of any
+//! size can be processed without holding it in memory. With
+//! the `mmap` feature, `input_map()` memory-maps `stdin`
+//! when it is a regular file, and `input_file_map()` maps a
+//! named file.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use aoc::ByteReader;
+//!
+//! let mut bytes = ByteReader::new(&b"(3x2)abcd"[..]);
+//! assert_eq!(bytes.next(), Some(b'('));
+//! assert_eq!(bytes.read_until(b')'), Some(b"3x2".to_vec()));
+//! assert_eq!(bytes.skip_bytes(3), 3);
+//! assert_eq!(bytes.peek(), Some(b'd'));
+//! assert_eq!(bytes.offset(), 8);
+//! assert_eq!(bytes.collect::<Vec<u8>>(), b"d");
+//! ```
+
+use std::io::*;
+
+/// Read all of `stdin` as bytes.
+///
+/// # Panics
+///
+/// Panics on a read error.
+pub fn input_bytes() -> Vec<u8> {
+    let mut bytes = Vec::new();
+    let _ = stdin()
+        .read_to_end(&mut bytes)
+        .expect("could not read input");
+    bytes
+}
+
+/// Read all of `stdin` as a string.
+///
+/// # Panics
+///
+/// Panics on a read error or if the input is not UTF-8.
+pub fn input_string() -> String {
+    let mut s = String::new();
+    let _ = stdin()
+        .read_to_string(&mut s)
+        .expect("could not read input");
+    s
+}
+
+/// Memory-map `stdin`, returning an error if it is not a
+/// mappable file.
+#[cfg(feature = "mmap")]
+pub fn input_map() -> Result<memmap2::Mmap> {
+    // Safety: the mapping is read-only, and the puzzle
+    // input is not going to change under us.
+    unsafe { memmap2::Mmap::map(&stdin()) }
+}
+
+/// Memory-map the file with the given filename, returning
+/// an error on failure to open or map it.
+#[cfg(feature = "mmap")]
+pub fn input_file_map(filename: &str) -> Result<memmap2::Mmap> {
+    let file = std::fs::File::open(filename)?;
+    // Safety: as for `input_map()`.
+    unsafe { memmap2::Mmap::map(&file) }
+}
+
+/// Buffered byte-at-a-time reader with one byte of
+/// lookahead.
+pub struct ByteReader<R: Read> {
+    /// The input.
+    reader: BufReader<R>,
+    /// Number of bytes consumed so far.
+    offset: u64,
+}
+
+impl<R: Read> ByteReader<R> {
+    /// Read bytes from `reader`.
+    pub fn new(reader: R) -> Self {
+        ByteReader {
+            reader: BufReader::new(reader),
+            offset: 0,
+        }
+    }
+
+    /// The buffered input, refilled if empty. Empty only
+    /// at end of input.
+    ///
+    /// # Panics
+    ///
+    /// Panics on a read error.
+    fn buffer(&mut self) -> &[u8] {
+        self.reader.fill_buf().expect("could not read input")
+    }
+
+    /// Consume `n` buffered bytes.
+    fn consume(&mut self, n: usize) {
+        self.reader.consume(n);
+        self.offset += n as u64;
+    }
+
+    /// The next byte, without consuming it, or `None` at
+    /// end of input.
+    pub fn peek(&mut self) -> Option<u8> {
+        self.buffer().first().copied()
+    }
+
+    /// Number of bytes consumed so far.
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// Consume up to `n` bytes, stopping at end of input.
+    /// Returns the number of bytes consumed.
+    pub fn skip_bytes(&mut self, n: u64) -> u64 {
+        let mut skipped = 0;
+        while skipped < n {
+            let available = self.buffer().len() as u64;
+            if available == 0 {
+                break;
+            }
+            let step = available.min(n - skipped);
+            self.consume(step as usize);
+            skipped += step;
+        }
+        skipped
+    }
+
+    /// Consume bytes through the next `delim`, returning
+    /// the bytes before it, or `None` if end of input comes
+    /// first. The bytes are consumed either way.
+    pub fn read_until(&mut self, delim: u8) -> Option<Vec<u8>> {
+        let mut bytes = Vec::new();
+        let n = self
+            .reader
+            .read_until(delim, &mut bytes)
+            .expect("could not read input");
+        self.offset += n as u64;
+        match bytes.pop() {
+            Some(b) if b == delim => Some(bytes),
+            _ => None,
+        }
+    }
+}
+
+impl<R: Read> Iterator for ByteReader<R> {
+    type Item = u8;
+
+    /// Consume and return the next byte, if any.
+    ///
+    /// # Panics
+    ///
+    /// Panics on a read error.
+    fn next(&mut self) -> Option<u8> {
+        let byte = self.peek()?;
+        self.consume(1);
+        Some(byte)
+    }
+}
+
+/// Get a new byte reader over `stdin`.
+pub fn input_byte_reader() -> ByteReader<Stdin> {
+    ByteReader::new(stdin())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Reader that returns at most three bytes per read,
+    /// to exercise buffer refills.
+    struct Trickle<'a>(&'a [u8]);
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+            let n = buf.len().min(3).min(self.0.len());
+            buf[..n].copy_from_slice(&self.0[..n]);
+            self.0 = &self.0[n..];
+            Ok(n)
+        }
+    }
+
+    #[test]
+    fn test_byte_reader() {
+        let mut bytes = ByteReader::new(Trickle(b"abcdefg(12x3)hij"));
+        assert_eq!(bytes.peek(), Some(b'a'));
+        assert_eq!(bytes.peek(), Some(b'a'));
+        assert_eq!(bytes.next(), Some(b'a'));
+        assert_eq!(bytes.skip_bytes(5), 5);
+        assert_eq!(bytes.next(), Some(b'g'));
+        assert_eq!(bytes.read_until(b')'), Some(b"(12x3".to_vec()));
+        assert_eq!(bytes.offset(), 13);
+        assert_eq!(bytes.read_until(b')'), None);
+        assert_eq!(bytes.offset(), 16);
+        assert_eq!(bytes.peek(), None);
+        assert_eq!(bytes.skip_bytes(10), 0);
+        assert_eq!(bytes.next(), None);
+    }
+
+    #[test]
+    fn test_skip_past_end() {
+        let mut bytes = ByteReader::new(Trickle(b"abcdefg"));
+        assert_eq!(bytes.skip_bytes(100), 7);
+        assert_eq!(bytes.offset(), 7);
+    }
+
+    #[cfg(feature = "mmap")]
+    #[test]
+    fn test_file_map() {
+        let path = std::env::temp_dir().join(format!("aoc-map-{}.txt", std::process::id()));
+        std::fs::write(&path, b"(6x1)(1x3)A\n").unwrap();
+        let map = input_file_map(path.to_str().unwrap()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(&map[..], b"(6x1)(1x3)A\n");
+        assert!(input_file_map("/nonexistent/aoc").is_err());
+    }
+}
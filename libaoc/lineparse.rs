@@ -0,0 +1,690 @@
+// Copyright © 2016 Bart Massey
+// This program is licensed under the "MIT License".
+// Please see the file COPYING in this distribution
+// for license terms.
+
+//! Declarative parsing of puzzle input lines.
+//!
+//! A `LinePattern` is a line of literal text with named
+//! fields in braces, like
+//!
+//! ```text
+//! Disc #{disc:usize} has {npos:usize} positions; at time=0, it is at position {start:usize}.
+//! ```
+//!
+//! A field may name a type after a colon, which restricts
+//! what text it can match: the unsigned integer types match
+//! digits, the signed integer types digits with an optional
+//! sign, `char` a single character and `word` a run of
+//! non-whitespace. An untyped field matches any non-empty
+//! text that neither starts nor ends with whitespace.
+//! Whitespace in the pattern matches any non-empty
+//! run of whitespace, and `{{` and `}}` stand for literal
+//! braces. Fields named `_` are matched but not extracted.
+//! Fields match as much as they can while still letting
+//! the rest of the pattern match.
+//!
+//! A matched line is a `Record`, whose fields are parsed on
+//! request with `FromStr`. `FromRecord` types can be built
+//! from a whole record: tuples take the fields in order,
+//! and structs can implement it by hand using
+//! `Record::get()`. `parse_lines()` runs a line parser over
+//! the lines of the input, numbering them, so that a failure
+//! reports both the line and the field that failed.
+//!
+//! For input with several kinds of line, `#[derive(FromLine)]`
+//! (from the `libfromline` crate) gives an enum a `FromStr`
+//! impl from a `#[pattern]` on each variant. In a tuple
+//! variant, `{}` fields are filled in order; in a struct
+//! variant, fields are named after the variant's fields.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use aoc::LinePattern;
+//!
+//! let pattern = LinePattern::new("{name}-{sector:u32}[{checksum}]");
+//! let (name, sector, checksum): (String, u32, String) =
+//!     pattern.parse_line("aaaaa-bbb-z-y-x-123[abxyz]").unwrap();
+//! assert_eq!((name.as_str(), sector, checksum.as_str()), ("aaaaa-bbb-z-y-x", 123, "abxyz"));
+//!
+//! let pattern = LinePattern::new("bot {bot:usize} gives {chip}");
+//! let lines = ["bot 1 gives 3", "bot 2 gives many"];
+//! let parsed: Result<Vec<(usize, u8)>, _> =
+//!     aoc::parse_lines(lines, |line| pattern.parse_line(line)).collect();
+//! assert_eq!(
+//!     parsed.unwrap_err().to_string(),
+//!     "line 2: field `chip` (\"many\"): invalid digit found in string",
+//! );
+//!
+//! #[derive(aoc::FromLine, Debug, PartialEq)]
+//! enum Insn {
+//!     #[pattern("swap position {} with position {}")]
+//!     Swap(usize, usize),
+//!     #[pattern("rotate left {} step")]
+//!     #[pattern("rotate left {} steps")]
+//!     RotL(usize),
+//!     #[pattern("move {count} from {from:char}")]
+//!     Move { from: char, count: usize },
+//!     #[pattern("halt")]
+//!     Halt,
+//! }
+//!
+//! assert_eq!("rotate left 1 step".parse(), Ok(Insn::RotL(1)));
+//! assert_eq!("move 3 from x".parse(), Ok(Insn::Move { from: 'x', count: 3 }));
+//! assert_eq!(
+//!     "swap position 1 with position b".parse::<Insn>().unwrap_err().to_string(),
+//!     "field `Swap.1` (\"b\"): invalid digit found in string",
+//! );
+//! ```
+
+use std::fmt;
+use std::str::FromStr;
+
+pub use from_line::FromLine;
+
+/// Kinds of text a field can match.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum FieldClass {
+    /// Any non-empty text not starting or ending with
+    /// whitespace.
+    Any,
+    /// Decimal digits.
+    Unsigned,
+    /// Decimal digits with an optional sign.
+    Signed,
+    /// A single character.
+    Char,
+    /// Non-whitespace characters.
+    Word,
+}
+
+impl FieldClass {
+    /// The class for a type name in a pattern.
+    ///
+    /// # Panics
+    ///
+    /// Panics on an unknown type name.
+    fn from_type(name: &str) -> FieldClass {
+        match name {
+            "u8" | "u16" | "u32" | "u64" | "u128" | "usize" => FieldClass::Unsigned,
+            "i8" | "i16" | "i32" | "i64" | "i128" | "isize" => FieldClass::Signed,
+            "char" => FieldClass::Char,
+            "word" => FieldClass::Word,
+            "String" | "str" => FieldClass::Any,
+            _ => panic!("unknown field type {:?}", name),
+        }
+    }
+
+    /// Possible end positions of a field of this class
+    /// starting at the beginning of `text`, longest first.
+    fn ends(self, text: &str) -> Vec<usize> {
+        let run = |start: usize, keep: fn(char) -> bool| {
+            start
+                + text[start..]
+                    .find(|c: char| !keep(c))
+                    .unwrap_or(text.len() - start)
+        };
+        let (min, max) = match self {
+            FieldClass::Any => (1, text.len()),
+            FieldClass::Unsigned => (1, run(0, |c| c.is_ascii_digit())),
+            FieldClass::Signed => {
+                let sign = usize::from(text.starts_with(['+', '-']));
+                (sign + 1, run(sign, |c| c.is_ascii_digit()))
+            }
+            FieldClass::Char => {
+                let n = text.chars().next().map_or(0, char::len_utf8);
+                (n.max(1), n)
+            }
+            FieldClass::Word => (1, run(0, |c| !c.is_whitespace())),
+        };
+        if self == FieldClass::Any && text.starts_with(char::is_whitespace) {
+            return Vec::new();
+        }
+        (min..=max)
+            .rev()
+            .filter(|&end| text.is_char_boundary(end))
+            .filter(|&end| self != FieldClass::Any || !text[..end].ends_with(char::is_whitespace))
+            .collect()
+    }
+}
+
+/// Piece of a `LinePattern`.
+#[derive(Clone, Debug)]
+enum Piece {
+    /// Literal text, without whitespace.
+    Literal(String),
+    /// A run of whitespace.
+    Space,
+    /// A field, matching the given class of text.
+    Field(FieldClass),
+}
+
+/// Pattern for a line with named fields. See the module
+/// documentation for the syntax.
+#[derive(Clone, Debug)]
+pub struct LinePattern {
+    /// The pattern text.
+    text: String,
+    /// The pieces to match in order.
+    pieces: Vec<Piece>,
+    /// Field names, in order.
+    names: Vec<String>,
+}
+
+impl LinePattern {
+    /// Compile the given pattern.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the pattern is malformed: unbalanced
+    /// braces, or a field with an unknown type or a
+    /// duplicate name.
+    pub fn new(text: &str) -> Self {
+        let mut pieces = Vec::new();
+        let mut names: Vec<String> = Vec::new();
+        let mut literal = String::new();
+        let mut chars = text.chars().peekable();
+        let flush = |literal: &mut String, pieces: &mut Vec<Piece>| {
+            if !literal.is_empty() {
+                pieces.push(Piece::Literal(std::mem::take(literal)));
+            }
+        };
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    literal.push('}');
+                }
+                '{' => {
+                    flush(&mut literal, &mut pieces);
+                    let mut field = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => field.push(c),
+                            None => panic!("unclosed field in pattern {:?}", text),
+                        }
+                    }
+                    let (name, class) = match field.split_once(':') {
+                        Some((name, ty)) => (name.trim(), FieldClass::from_type(ty.trim())),
+                        None => (field.trim(), FieldClass::Any),
+                    };
+                    assert!(!name.is_empty(), "unnamed field in pattern {:?}", text);
+                    assert!(
+                        name == "_" || !names.iter().any(|n| n == name),
+                        "duplicate field {:?} in pattern {:?}",
+                        name,
+                        text,
+                    );
+                    pieces.push(Piece::Field(class));
+                    names.push(name.to_string());
+                }
+                '}' => panic!("unmatched }} in pattern {:?}", text),
+                c if c.is_whitespace() => {
+                    flush(&mut literal, &mut pieces);
+                    while chars.peek().is_some_and(|c| c.is_whitespace()) {
+                        chars.next();
+                    }
+                    pieces.push(Piece::Space);
+                }
+                c => literal.push(c),
+            }
+        }
+        flush(&mut literal, &mut pieces);
+        LinePattern {
+            text: text.to_string(),
+            pieces,
+            names,
+        }
+    }
+
+    /// Names of the extracted fields, in order.
+    pub fn fields(&self) -> impl Iterator<Item = &str> {
+        self.names.iter().map(String::as_str).filter(|&n| n != "_")
+    }
+
+    /// Try to match `pieces[k..]` against `line[posn..]`,
+    /// recording field spans.
+    fn match_from(
+        &self,
+        k: usize,
+        line: &str,
+        posn: usize,
+        spans: &mut Vec<(usize, usize)>,
+    ) -> bool {
+        let rest = &line[posn..];
+        match self.pieces.get(k) {
+            None => rest.is_empty(),
+            Some(Piece::Literal(s)) => {
+                rest.starts_with(s.as_str()) && self.match_from(k + 1, line, posn + s.len(), spans)
+            }
+            Some(Piece::Space) => {
+                let n = rest.len() - rest.trim_start().len();
+                n > 0 && self.match_from(k + 1, line, posn + n, spans)
+            }
+            Some(&Piece::Field(class)) => {
+                for end in class.ends(rest) {
+                    spans.push((posn, posn + end));
+                    if self.match_from(k + 1, line, posn + end, spans) {
+                        return true;
+                    }
+                    spans.pop();
+                }
+                false
+            }
+        }
+    }
+
+    /// Match `line` against the pattern, returning its
+    /// fields if it matches.
+    pub fn match_line<'p, 'l>(&'p self, line: &'l str) -> Option<Record<'p, 'l>> {
+        let mut spans = Vec::with_capacity(self.names.len());
+        if !self.match_from(0, line, 0, &mut spans) {
+            return None;
+        }
+        Some(Record {
+            pattern: self,
+            line,
+            spans,
+            line_no: None,
+        })
+    }
+
+    /// Match `line` against the pattern and build a `T` from
+    /// its fields.
+    pub fn parse_line<T: FromRecord>(&self, line: &str) -> Result<T, LineError> {
+        match self.match_line(line) {
+            Some(record) => record.parse(),
+            None => Err(LineError::no_match(line)),
+        }
+    }
+}
+
+/// Fields of a line matched by a `LinePattern`.
+#[derive(Clone, Debug)]
+pub struct Record<'p, 'l> {
+    /// The pattern matched.
+    pattern: &'p LinePattern,
+    /// The line matched.
+    line: &'l str,
+    /// Byte range of each field in the line.
+    spans: Vec<(usize, usize)>,
+    /// Line number for error reports, if known.
+    line_no: Option<usize>,
+}
+
+impl<'l> Record<'_, 'l> {
+    /// Mark the record as coming from line `line_no`, for
+    /// error reports.
+    pub fn at_line(mut self, line_no: usize) -> Self {
+        self.line_no = Some(line_no);
+        self
+    }
+
+    /// The whole line.
+    pub fn line(&self) -> &'l str {
+        self.line
+    }
+
+    /// Index of the field called `name`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the pattern has no such field.
+    fn index(&self, name: &str) -> usize {
+        self.pattern
+            .names
+            .iter()
+            .position(|n| n == name)
+            .unwrap_or_else(|| panic!("no field {:?} in pattern {:?}", name, self.pattern.text))
+    }
+
+    /// Text of field `i`.
+    fn text(&self, i: usize) -> &'l str {
+        let (start, end) = self.spans[i];
+        &self.line[start..end]
+    }
+
+    /// Parse field `i` as a `T`.
+    fn parse_field<T>(&self, i: usize) -> Result<T, LineError>
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+    {
+        let text = self.text(i);
+        text.parse().map_err(|e: T::Err| LineError {
+            line_no: self.line_no,
+            kind: LineErrorKind::BadField {
+                field: self.pattern.names[i].clone(),
+                text: text.to_string(),
+                reason: e.to_string(),
+            },
+        })
+    }
+
+    /// Text of the field called `name`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the pattern has no such field.
+    pub fn str(&self, name: &str) -> &'l str {
+        self.text(self.index(name))
+    }
+
+    /// Parse the field called `name` as a `T`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the pattern has no such field.
+    pub fn get<T>(&self, name: &str) -> Result<T, LineError>
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+    {
+        self.parse_field(self.index(name))
+    }
+
+    /// Build a `T` from the fields.
+    pub fn parse<T: FromRecord>(&self) -> Result<T, LineError> {
+        T::from_record(self)
+    }
+}
+
+/// Types that can be built from the fields of a `Record`.
+pub trait FromRecord: Sized {
+    /// Build a value from `record`.
+    fn from_record(record: &Record) -> Result<Self, LineError>;
+}
+
+/// Implement `FromRecord` for tuples, taking the extracted
+/// fields in order.
+macro_rules! tuple_from_record {
+    ($n:expr; $($t:ident),+) => {
+        impl<$($t),+> FromRecord for ($($t,)+)
+        where
+            $($t: FromStr, $t::Err: fmt::Display),+
+        {
+            fn from_record(record: &Record) -> Result<Self, LineError> {
+                let mut fields = (0..record.spans.len())
+                    .filter(|&i| record.pattern.names[i] != "_");
+                let nfields = fields.clone().count();
+                assert_eq!(
+                    nfields, $n,
+                    "pattern {:?} has {} fields, not {}",
+                    record.pattern.text, nfields, $n,
+                );
+                Ok(($(record.parse_field::<$t>(fields.next().unwrap())?,)+))
+            }
+        }
+    };
+}
+
+tuple_from_record!(1; A);
+tuple_from_record!(2; A, B);
+tuple_from_record!(3; A, B, C);
+tuple_from_record!(4; A, B, C, D);
+tuple_from_record!(5; A, B, C, D, E);
+tuple_from_record!(6; A, B, C, D, E, F);
+
+/// What went wrong parsing a line.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LineErrorKind {
+    /// The line matches no pattern.
+    NoMatch(String),
+    /// A field of the line did not parse.
+    BadField {
+        /// Name of the field.
+        field: String,
+        /// Text of the field.
+        text: String,
+        /// The field parser's complaint.
+        reason: String,
+    },
+}
+
+/// Error from parsing a line.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LineError {
+    /// Line number, counting from 1, if known.
+    pub line_no: Option<usize>,
+    /// What went wrong.
+    pub kind: LineErrorKind,
+}
+
+impl LineError {
+    /// Error for a `line` that matches no pattern.
+    pub fn no_match(line: &str) -> Self {
+        LineError {
+            line_no: None,
+            kind: LineErrorKind::NoMatch(line.to_string()),
+        }
+    }
+
+    /// The same error, reported at `line_no` unless it
+    /// already has a line number.
+    pub fn at_line(mut self, line_no: usize) -> Self {
+        self.line_no.get_or_insert(line_no);
+        self
+    }
+}
+
+impl fmt::Display for LineError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(line_no) = self.line_no {
+            write!(f, "line {}: ", line_no)?;
+        }
+        match &self.kind {
+            LineErrorKind::NoMatch(line) => write!(f, "unrecognized line {:?}", line),
+            LineErrorKind::BadField {
+                field,
+                text,
+                reason,
+            } => write!(f, "field `{}` ({:?}): {}", field, text, reason),
+        }
+    }
+}
+
+impl std::error::Error for LineError {}
+
+/// Iterator over the results of parsing numbered lines. See
+/// `parse_lines()`.
+pub struct ParseLines<I, F> {
+    /// The lines.
+    lines: I,
+    /// The line parser.
+    parse: F,
+    /// Number of lines read so far.
+    line_no: usize,
+    /// Drop lines that match no pattern rather than
+    /// reporting them.
+    skip_unmatched: bool,
+}
+
+/// Parse each of `lines` with `parse`, yielding the
+/// results. Errors are tagged with their line number,
+/// counting from 1.
+pub fn parse_lines<I, F, T>(lines: I, parse: F) -> ParseLines<I::IntoIter, F>
+where
+    I: IntoIterator,
+    I::Item: AsRef<str>,
+    F: FnMut(&str) -> Result<T, LineError>,
+{
+    ParseLines {
+        lines: lines.into_iter(),
+        parse,
+        line_no: 0,
+        skip_unmatched: false,
+    }
+}
+
+impl<I, F> ParseLines<I, F> {
+    /// Silently drop lines that match no pattern, such as
+    /// headers.
+    pub fn skip_unmatched(mut self) -> Self {
+        self.skip_unmatched = true;
+        self
+    }
+}
+
+impl<I, F, T> Iterator for ParseLines<I, F>
+where
+    I: Iterator,
+    I::Item: AsRef<str>,
+    F: FnMut(&str) -> Result<T, LineError>,
+{
+    type Item = Result<T, LineError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let line = self.lines.next()?;
+            self.line_no += 1;
+            match (self.parse)(line.as_ref()) {
+                Err(LineError {
+                    kind: LineErrorKind::NoMatch(_),
+                    ..
+                }) if self.skip_unmatched => continue,
+                result => return Some(result.map_err(|e| e.at_line(self.line_no))),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_match_fields() {
+        let pattern = LinePattern::new(
+            "/dev/grid/node-x{x:usize}-y{y:usize} {_:usize}T {used:usize}T {avail}T {_}%",
+        );
+        assert_eq!(
+            pattern.fields().collect::<Vec<_>>(),
+            ["x", "y", "used", "avail"]
+        );
+        let record = pattern
+            .match_line("/dev/grid/node-x12-y3     94T   65T    29T   69%")
+            .unwrap();
+        assert_eq!(record.get::<usize>("x"), Ok(12));
+        assert_eq!(record.str("avail"), "29");
+        assert_eq!(record.parse::<(u8, u8, u8, u8)>(), Ok((12, 3, 65, 29)));
+        assert!(pattern
+            .match_line("Filesystem Size Used Avail Use%")
+            .is_none());
+    }
+
+    #[test]
+    fn test_field_classes() {
+        let pattern = LinePattern::new("{a:i32}{b:word} {c:char}{d}");
+        let (a, b, c, d): (i32, String, char, String) = pattern.parse_line("-12x3  éz {").unwrap();
+        assert_eq!((a, b.as_str(), c, d.as_str()), (-12, "x3", 'é', "z {"));
+        assert!(pattern.match_line("-12x3  éz ").is_none());
+        assert!(pattern.match_line("+x ab").is_none());
+        assert!(pattern.match_line("1x a").is_none());
+
+        let pattern = LinePattern::new("{{{n:u8}}}");
+        assert_eq!(pattern.parse_line("{17}"), Ok((17u8,)));
+        assert!(pattern.match_line("{-17}").is_none());
+    }
+
+    #[test]
+    fn test_greedy_backtracking() {
+        let pattern = LinePattern::new("{name}-{sector:u32}[{sum}]");
+        let record = pattern.match_line("a-b-1-c-404[xy]z]").unwrap();
+        assert_eq!(record.str("name"), "a-b-1-c");
+        assert_eq!(record.str("sum"), "xy]z");
+    }
+
+    #[test]
+    fn test_errors() {
+        let pattern = LinePattern::new("value {v} goes to bot {bot}");
+        let lines = ["value 5 goes to bot 2", "value 3 goes to bot x", "bot 1"];
+        let results: Vec<Result<(u32, u32), LineError>> =
+            parse_lines(lines, |line| pattern.parse_line(line)).collect();
+        assert_eq!(results[0], Ok((5, 2)));
+        assert_eq!(
+            results[1].clone().unwrap_err().to_string(),
+            "line 2: field `bot` (\"x\"): invalid digit found in string",
+        );
+        assert_eq!(
+            results[2],
+            Err(LineError {
+                line_no: Some(3),
+                kind: LineErrorKind::NoMatch("bot 1".to_string()),
+            }),
+        );
+        let skipped: Vec<(u32, u32)> = parse_lines(["bot 1", "value 1 goes to bot 1"], |line| {
+            pattern.parse_line(line)
+        })
+        .skip_unmatched()
+        .collect::<Result<_, _>>()
+        .unwrap();
+        assert_eq!(skipped, [(1, 1)]);
+    }
+
+    /// Instructions in the style of Day 21.
+    #[derive(FromLine, Debug, PartialEq)]
+    enum Insn {
+        #[pattern("swap letter {:char} with letter {:char}")]
+        SwChar(char, char),
+        #[pattern("rotate {_} {:usize} step")]
+        #[pattern("rotate {_} {:usize} steps")]
+        Rot(usize),
+        #[pattern("reverse positions {start} through {end}")]
+        Rev { end: usize, start: usize },
+        #[pattern("{{nop}}")]
+        Nop,
+    }
+
+    #[test]
+    fn test_derive() {
+        let lines = [
+            "swap letter a with letter é",
+            "rotate left 1 step",
+            "rotate right 10 steps",
+            "reverse positions 4 through 6",
+            "{nop}",
+        ];
+        let insns: Vec<Insn> = parse_lines(lines, str::parse)
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(
+            insns,
+            [
+                Insn::SwChar('a', 'é'),
+                Insn::Rot(1),
+                Insn::Rot(10),
+                Insn::Rev { end: 6, start: 4 },
+                Insn::Nop,
+            ],
+        );
+
+        let errors: Vec<String> = parse_lines(
+            [
+                "swap letter ab with letter c",
+                "reverse positions 1 through x",
+            ],
+            str::parse::<Insn>,
+        )
+        .map(|r| r.unwrap_err().to_string())
+        .collect();
+        assert_eq!(
+            errors,
+            [
+                "line 1: unrecognized line \"swap letter ab with letter c\"",
+                "line 2: field `end` (\"x\"): invalid digit found in string",
+            ],
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "unknown field type")]
+    fn test_bad_type() {
+        LinePattern::new("{x:float}");
+    }
+}
@@ -10,9 +10,47 @@
 //! thin wrapper over existing stuff. But it provides
 //! a bit of boilerplate removal and isolates a whole
 //! bunch of dependencies.
+//!
+//! `InputLines` panics on read errors; `TryInputLines`
+//! yields the first one instead, and then ends. Either can
+//! be `numbered()`, so that later errors can say where they
+//! happened. The `LinesExt` adapters then drop blank and
+//! comment lines or gather lines into blank-separated
+//! paragraphs, keeping the numbers of the lines they pass
+//! along. Over `TryInputLines` they pass read errors along
+//! too.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use aoc::{InputLines, LinesExt};
+//!
+//! let text = "# Rooms.\n\nfirst 1\nsecond 2\n\n\nthird x\n";
+//! let lines: Vec<(usize, String)> = InputLines::new(text.as_bytes())
+//!     .numbered()
+//!     .skip_blank()
+//!     .skip_comments("#")
+//!     .collect();
+//! assert_eq!(lines[0], (3, "first 1".to_string()));
+//!
+//! // A parse error can report its line.
+//! let pattern = aoc::LinePattern::new("{name} {number}");
+//! let bad = lines
+//!     .iter()
+//!     .map(|(n, l)| pattern.parse_line::<(String, u32)>(l).map_err(|e| e.at_line(*n)))
+//!     .find_map(Result::err)
+//!     .unwrap();
+//! assert_eq!(bad.to_string(), "line 7: field `number` (\"x\"): invalid digit found in string");
+//!
+//! let paragraphs: Vec<Vec<String>> = InputLines::new(text.as_bytes()).paragraphs().collect();
+//! assert_eq!(paragraphs.len(), 3);
+//! assert_eq!(paragraphs[1], ["first 1", "second 2"]);
+//! ```
 
 use std::fs::File;
 use std::io::*;
+use std::iter::Zip;
+use std::ops::RangeFrom;
 
 /// The `std::io::Lines` iterator, wrapped so that
 /// it will `panic!()` on failure rather than returning
@@ -28,6 +66,20 @@ impl<T: Read> InputLines<T> {
             lines: BufReader::new(file).lines(),
         }
     }
+
+    /// Pair each line with its line number, counting
+    /// from 1.
+    pub fn numbered(self) -> Zip<RangeFrom<usize>, Self> {
+        (1..).zip(self)
+    }
+
+    /// Yield read errors rather than panicking on them.
+    pub fn fallible(self) -> TryInputLines<T> {
+        TryInputLines {
+            lines: self.lines,
+            failed: false,
+        }
+    }
 }
 
 impl<T: Read> Iterator for InputLines<T> {
@@ -45,11 +97,53 @@ impl<T: Read> Iterator for InputLines<T> {
     }
 }
 
+/// The `std::io::Lines` iterator over a buffered reader:
+/// lines of input, or an error in reading them. Iteration
+/// ends after the first error, since a broken reader would
+/// otherwise keep returning it.
+pub struct TryInputLines<T: Read> {
+    lines: Lines<BufReader<T>>,
+    /// A read error has been returned.
+    failed: bool,
+}
+
+impl<T: Read> TryInputLines<T> {
+    /// Return the wrapped `std::io::Lines` iterator.
+    pub fn new(file: T) -> Self {
+        InputLines::new(file).fallible()
+    }
+
+    /// Pair each line, or error, with its line number,
+    /// counting from 1.
+    pub fn numbered(self) -> Zip<RangeFrom<usize>, Self> {
+        (1..).zip(self)
+    }
+}
+
+impl<T: Read> Iterator for TryInputLines<T> {
+    type Item = Result<String>;
+    /// Return the next line or read error if any.
+    fn next(&mut self) -> Option<Result<String>> {
+        if self.failed {
+            return None;
+        }
+        let line = self.lines.next();
+        self.failed = matches!(line, Some(Err(_)));
+        line
+    }
+}
+
 /// Get a new iterator over lines of `stdin`.
 pub fn input_lines() -> InputLines<Stdin> {
     InputLines::new(stdin())
 }
 
+/// Get a new iterator over lines of `stdin` that yields
+/// read errors rather than panicking on them.
+pub fn try_input_lines() -> TryInputLines<Stdin> {
+    TryInputLines::new(stdin())
+}
+
 /// Get a new iterator over lines of the file with the given
 /// filename, returning an error on failure to open the
 /// file.
@@ -57,3 +151,213 @@ pub fn input_file_lines(filename: &str) -> Result<InputLines<File>> {
     let file = File::open(filename)?;
     Ok(InputLines::new(file))
 }
+
+/// A line of input as yielded by a lines iterator: the
+/// line itself, the result of reading it, or a numbered
+/// line.
+pub trait LineText {
+    /// The text of the line, or `None` for a read error.
+    fn text(&self) -> Option<&str>;
+
+    /// The line is empty or all whitespace. Read errors are
+    /// not blank.
+    fn is_blank(&self) -> bool {
+        self.text().is_some_and(|t| t.trim().is_empty())
+    }
+}
+
+impl LineText for String {
+    fn text(&self) -> Option<&str> {
+        Some(self)
+    }
+}
+
+impl LineText for &str {
+    fn text(&self) -> Option<&str> {
+        Some(self)
+    }
+}
+
+impl<L: LineText> LineText for Result<L> {
+    fn text(&self) -> Option<&str> {
+        self.as_ref().ok()?.text()
+    }
+}
+
+impl<L: LineText> LineText for (usize, L) {
+    fn text(&self) -> Option<&str> {
+        self.1.text()
+    }
+}
+
+/// Adapters for iterators over lines of input.
+pub trait LinesExt: Iterator + Sized
+where
+    Self::Item: LineText,
+{
+    /// Drop lines that are empty or all whitespace.
+    fn skip_blank(self) -> impl Iterator<Item = Self::Item> {
+        self.filter(|line| !line.is_blank())
+    }
+
+    /// Drop lines whose first non-whitespace text is
+    /// `prefix`.
+    fn skip_comments(self, prefix: &str) -> impl Iterator<Item = Self::Item> {
+        let prefix = prefix.to_string();
+        self.filter(move |line| {
+            !line
+                .text()
+                .is_some_and(|t| t.trim_start().starts_with(&prefix))
+        })
+    }
+
+    /// Gather lines into paragraphs: runs of lines
+    /// separated by one or more blank lines. The blank
+    /// lines themselves are dropped. A read error ends its
+    /// paragraph, as the paragraph's last line.
+    fn paragraphs(self) -> Paragraphs<Self> {
+        Paragraphs { lines: self }
+    }
+}
+
+impl<I> LinesExt for I
+where
+    I: Iterator,
+    I::Item: LineText,
+{
+}
+
+/// Iterator over the paragraphs of its lines. See
+/// `LinesExt::paragraphs()`.
+pub struct Paragraphs<I> {
+    /// The lines.
+    lines: I,
+}
+
+impl<I> Iterator for Paragraphs<I>
+where
+    I: Iterator,
+    I::Item: LineText,
+{
+    type Item = Vec<I::Item>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut paragraph = Vec::new();
+        for line in self.lines.by_ref() {
+            if line.text().is_none() {
+                paragraph.push(line);
+                break;
+            } else if !line.is_blank() {
+                paragraph.push(line);
+            } else if !paragraph.is_empty() {
+                break;
+            }
+        }
+        if paragraph.is_empty() {
+            None
+        } else {
+            Some(paragraph)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Reader that fails after yielding its text.
+    struct Failing(&'static [u8]);
+
+    impl Read for Failing {
+        fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+            if self.0.is_empty() {
+                return Err(Error::other("disk on fire"));
+            }
+            self.0.read(buf)
+        }
+    }
+
+    #[test]
+    fn test_try_numbered() {
+        let mut lines = TryInputLines::new(Failing(b"a\nb\n")).numbered();
+        assert_eq!(
+            lines.next().map(|(n, l)| (n, l.unwrap())),
+            Some((1, "a".to_string()))
+        );
+        assert_eq!(
+            lines.next().map(|(n, l)| (n, l.unwrap())),
+            Some((2, "b".to_string()))
+        );
+        let (n, error) = lines.next().unwrap();
+        assert_eq!(
+            (n, error.unwrap_err().to_string()),
+            (3, "disk on fire".to_string())
+        );
+        assert!(lines.next().is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "could not read input line")]
+    fn test_panic_on_error() {
+        let _ = InputLines::new(Failing(b"a\n")).count();
+    }
+
+    #[test]
+    fn test_skip() {
+        let lines = ["  // header", "", "x = 1", "   ", "y = 2 // two", "//"];
+        let kept: Vec<_> = (1..).zip(lines).skip_comments("//").skip_blank().collect();
+        assert_eq!(kept, [(3, "x = 1"), (5, "y = 2 // two")]);
+    }
+
+    #[test]
+    fn test_paragraphs() {
+        let text = "\n\na\nb\n \n\nc\n\nd\ne\n\n";
+        let paragraphs: Vec<Vec<(usize, String)>> = InputLines::new(text.as_bytes())
+            .numbered()
+            .paragraphs()
+            .collect();
+        let numbers: Vec<Vec<usize>> = paragraphs
+            .iter()
+            .map(|p| p.iter().map(|(n, _)| *n).collect())
+            .collect();
+        assert_eq!(numbers, [vec![3, 4], vec![7], vec![9, 10]]);
+        assert_eq!(paragraphs[2][1].1, "e");
+        assert_eq!(InputLines::new(&b"\n  \n"[..]).paragraphs().count(), 0);
+    }
+
+    #[test]
+    fn test_try_paragraphs() {
+        let mut paragraphs = TryInputLines::new(Failing(b"a\n\n\nb\nc\n"))
+            .numbered()
+            .skip_blank()
+            .paragraphs();
+        let text = |p: Vec<(usize, Result<String>)>| -> Vec<(usize, String)> {
+            p.into_iter()
+                .map(|(n, l)| (n, l.unwrap_or_else(|e| e.to_string())))
+                .collect()
+        };
+        // Blank lines are gone before `paragraphs()` sees
+        // them, so everything up to the error is one
+        // paragraph.
+        assert_eq!(
+            paragraphs.next().map(text),
+            Some(vec![
+                (1, "a".to_string()),
+                (4, "b".to_string()),
+                (5, "c".to_string()),
+                (6, "disk on fire".to_string()),
+            ])
+        );
+        assert!(paragraphs.next().is_none());
+
+        let paragraphs: Vec<Vec<Result<String>>> = TryInputLines::new(Failing(b"a\n\nb\n"))
+            .skip_comments("#")
+            .paragraphs()
+            .collect();
+        let errors: Vec<Vec<bool>> = paragraphs
+            .iter()
+            .map(|p| p.iter().map(Result::is_err).collect())
+            .collect();
+        assert_eq!(errors, [vec![false], vec![false, true]]);
+    }
+}
@@ -0,0 +1,211 @@
+// Copyright © 2016 Bart Massey
+// This program is licensed under the "MIT License".
+// Please see the file COPYING in this distribution
+// for license terms.
+
+//! Procedurally-generated mazes for Advent of Code 2016
+//! solutions.
+//!
+//! A `ProceduralMaze` is an unbounded (or clipped) grid
+//! whose walls are given by a function of location, as in
+//! the Day 13 office maze. Results of the function are
+//! cached, since they are typically asked for repeatedly
+//! during search. The maze can be searched by `a_star()` or
+//! `flood_fill()` using `MazePosn` as the search state.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use aoc::*;
+//!
+//! // Walls on every other column, except on row 3.
+//! let maze = ProceduralMaze::from_walls(|(x, y)| x % 2 == 1 && y != 3)
+//!     .with_goal((2, 0));
+//! assert_eq!(maze.render((0, 0), 4, 4), ".#.#\n.#.#\n.#.#\n....\n");
+//!
+//! let start = MazePosn((0, 0));
+//! let (dist, _) = a_star(&maze, &start, false).expect("no path");
+//! assert_eq!(dist, 8);
+//! assert_eq!(flood_fill(&maze, &start, 2).len(), 3);
+//! ```
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use crate::astar::SearchState;
+use crate::dirns::*;
+
+/// Maze with walls given by a function of location.
+pub struct ProceduralMaze {
+    /// Returns true for open locations.
+    open: Box<dyn Fn(Point) -> bool>,
+    /// Previously computed results of `open`.
+    cache: RefCell<HashMap<Point, bool>>,
+    /// Bounds of the maze.
+    grid_box: GridBox,
+    /// Target location for search, if any.
+    goal: Option<Point>,
+}
+
+impl ProceduralMaze {
+    /// Make an unbounded maze whose open locations are
+    /// those for which `open` returns true.
+    pub fn from_open<F>(open: F) -> Self
+    where
+        F: Fn(Point) -> bool + 'static,
+    {
+        ProceduralMaze {
+            open: Box::new(open),
+            cache: RefCell::new(HashMap::new()),
+            grid_box: GridBox::new_grid(),
+            goal: None,
+        }
+    }
+
+    /// Make an unbounded maze whose walls are those
+    /// locations for which `wall` returns true.
+    pub fn from_walls<F>(wall: F) -> Self
+    where
+        F: Fn(Point) -> bool + 'static,
+    {
+        Self::from_open(move |p| !wall(p))
+    }
+
+    /// Clip the maze to the given grid box.
+    pub fn with_grid_box(mut self, grid_box: GridBox) -> Self {
+        self.grid_box = grid_box;
+        self
+    }
+
+    /// Set the goal location for search.
+    pub fn with_goal(mut self, goal: Point) -> Self {
+        self.goal = Some(goal);
+        self
+    }
+
+    /// Return true iff the given location is open.
+    pub fn is_open(&self, loc: Point) -> bool {
+        if let Some(&open) = self.cache.borrow().get(&loc) {
+            return open;
+        }
+        let open = (self.open)(loc);
+        self.cache.borrow_mut().insert(loc, open);
+        open
+    }
+
+    /// Return true iff the given location is a wall.
+    pub fn is_wall(&self, loc: Point) -> bool {
+        !self.is_open(loc)
+    }
+
+    /// Return the open neighbors of the given location in
+    /// the cardinal directions.
+    pub fn open_neighbors(&self, loc: Point) -> Vec<Point> {
+        self.grid_box
+            .neighbors(loc, VonNeumann)
+            .filter(|&nb| self.is_open(nb))
+            .collect()
+    }
+
+    /// Render the window of the maze with the given
+    /// upper-left corner and size as text, with `#` for
+    /// walls and `.` for open locations. Each row is
+    /// terminated by a newline.
+    pub fn render(&self, (x0, y0): Point, width: usize, height: usize) -> String {
+        let window = Grid::new(width, height, ());
+        window.render(|(x, y), _| {
+            if self.is_open((x0 + x, y0 + y)) {
+                '.'
+            } else {
+                '#'
+            }
+        })
+    }
+}
+
+/// Search state for a `ProceduralMaze`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct MazePosn(pub Point);
+
+impl SearchState for MazePosn {
+    /// Paths are reported as locations.
+    type Label = Point;
+
+    /// The maze is the global data.
+    type Global = ProceduralMaze;
+
+    fn label(&self) -> Point {
+        self.0
+    }
+
+    /// Moves are to open neighbors at unit cost.
+    fn neighbors(&self, maze: &ProceduralMaze) -> Vec<(usize, Box<MazePosn>)> {
+        maze.open_neighbors(self.0)
+            .into_iter()
+            .map(|nb| (1, Box::new(MazePosn(nb))))
+            .collect()
+    }
+
+    /// The goal is the maze goal location, if any.
+    fn is_goal(&self, maze: &ProceduralMaze) -> bool {
+        maze.goal == Some(self.0)
+    }
+
+    /// The heuristic assumes no walls.
+    fn hcost(&self, maze: &ProceduralMaze) -> usize {
+        match maze.goal {
+            Some(goal) => manhattan_distance(self.0, goal),
+            None => 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    #[test]
+    fn test_cache() {
+        let calls = Rc::new(Cell::new(0));
+        let counter = Rc::clone(&calls);
+        let maze = ProceduralMaze::from_walls(move |(x, y)| {
+            counter.set(counter.get() + 1);
+            (x + y) % 3 == 0
+        });
+        assert!(maze.is_wall((1, 2)));
+        assert!(maze.is_open((1, 1)));
+        assert_eq!(calls.get(), 2);
+        assert!(maze.is_wall((1, 2)));
+        assert!(!maze.is_wall((1, 1)));
+        assert_eq!(calls.get(), 2);
+        let _ = maze.render((0, 0), 3, 3);
+        assert_eq!(calls.get(), 9);
+    }
+
+    #[test]
+    fn test_grid_box_clipping() {
+        let maze = ProceduralMaze::from_open(|_| true);
+        let mut nbs = maze.open_neighbors((1, 1));
+        nbs.sort();
+        assert_eq!(nbs, [(0, 1), (1, 0), (1, 2), (2, 1)]);
+
+        let maze = maze.with_grid_box(GridBox::new(2, 2)).with_goal((5, 5));
+        let mut nbs = maze.open_neighbors((1, 1));
+        nbs.sort();
+        assert_eq!(nbs, [(0, 1), (1, 0)]);
+        assert!(crate::a_star(&maze, &MazePosn((0, 0)), false).is_none());
+    }
+
+    #[test]
+    fn test_from_open() {
+        // Open only along the diagonal band x == y or
+        // x == y + 1.
+        let maze = ProceduralMaze::from_open(|(x, y)| x == y || x == y + 1).with_goal((3, 3));
+        assert_eq!(maze.render((0, 0), 4, 3), "..##\n#..#\n##..\n");
+        assert_eq!(maze.open_neighbors((1, 1)), [(1, 0), (2, 1)]);
+        let (dist, _) = crate::a_star(&maze, &MazePosn((0, 0)), false).expect("no path");
+        assert_eq!(dist, 6);
+    }
+}
@@ -0,0 +1,361 @@
+// Copyright © 2016 Bart Massey
+// This program is licensed under the "MIT License".
+// Please see the file COPYING in this distribution
+// for license terms.
+
+//! Multi-lane MD5: several independent messages hashed
+//! side by side.
+//!
+//! Brute-force hash searches spend nearly all their time
+//! running MD5 over short messages one at a time, which
+//! leaves most of a modern CPU's vector units idle. Here
+//! `N` messages are hashed together: each MD5 register is
+//! an array of `N` lanes, and every step of the compression
+//! function is a short loop over the lanes. There is no
+//! unsafe code and nothing specific to a CPU.
+//!
+//! Whether the lane loops become vector instructions is up
+//! to the compiler; `cargo bench --bench md5lanes` measures
+//! the difference from the `md-5` crate. An MD5
+//! `SeededHasher` hashes ranges of indices 8 at a time, so
+//! `scan_indices()` and `mine_zeros()` use the lanes.
+//!
+//! `md5_lanes()` takes any `N`; `md5x4()` and `md5x8()` are
+//! the widths that fit 128-bit and 256-bit vectors.
+//! `md5_indices()` is the common case for index scans: a
+//! fixed prefix followed by the decimal digits of
+//! consecutive indices. Messages may have any lengths,
+//! different in each lane, but the work is that of the
+//! longest, so batches of similar short messages do best.
+//!
+//! The digests are the same as those of the `md-5` crate.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use aoc::PuzzleHasher;
+//!
+//! let digests = aoc::md5x4([b"", b"a", b"abc", b"message digest"]);
+//! assert_eq!(aoc::hex_string(&digests[2]), "900150983cd24fb0d6963f7d28e17f72");
+//! assert_eq!(digests[3], aoc::Md5::digest(b"message digest"));
+//!
+//! let digests = aoc::md5_indices::<8>(b"abc", 3231925);
+//! assert_eq!(aoc::hex_string(&digests[4])[..6], *"000001");
+//! ```
+
+use std::ops::Range;
+
+use crate::hashsearch::{decimal, Md5Digest};
+
+/// Per-step additive constants, `floor(abs(sin(i + 1)) *
+/// 2^32)`.
+const K: [u32; 64] = [
+    0xd76aa478, 0xe8c7b756, 0x242070db, 0xc1bdceee, 0xf57c0faf, 0x4787c62a, 0xa8304613, 0xfd469501,
+    0x698098d8, 0x8b44f7af, 0xffff5bb1, 0x895cd7be, 0x6b901122, 0xfd987193, 0xa679438e, 0x49b40821,
+    0xf61e2562, 0xc040b340, 0x265e5a51, 0xe9b6c7aa, 0xd62f105d, 0x02441453, 0xd8a1e681, 0xe7d3fbc8,
+    0x21e1cde6, 0xc33707d6, 0xf4d50d87, 0x455a14ed, 0xa9e3e905, 0xfcefa3f8, 0x676f02d9, 0x8d2a4c8a,
+    0xfffa3942, 0x8771f681, 0x6d9d6122, 0xfde5380c, 0xa4beea44, 0x4bdecfa9, 0xf6bb4b60, 0xbebfbc70,
+    0x289b7ec6, 0xeaa127fa, 0xd4ef3085, 0x04881d05, 0xd9d4d039, 0xe6db99e5, 0x1fa27cf8, 0xc4ac5665,
+    0xf4292244, 0x432aff97, 0xab9423a7, 0xfc93a039, 0x655b59c3, 0x8f0ccc92, 0xffeff47d, 0x85845dd1,
+    0x6fa87e4f, 0xfe2ce6e0, 0xa3014314, 0x4e0811a1, 0xf7537e82, 0xbd3af235, 0x2ad7d2bb, 0xeb86d391,
+];
+
+/// Left-rotation amounts for each round, by step within
+/// the round mod 4.
+const S: [[u32; 4]; 4] = [
+    [7, 12, 17, 22],
+    [5, 9, 14, 20],
+    [4, 11, 16, 23],
+    [6, 10, 15, 21],
+];
+
+/// Initial register values.
+const INIT: [u32; 4] = [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476];
+
+/// One MD5 register across `N` lanes.
+type Lanes<const N: usize> = [u32; N];
+
+/// Index of the message word used by step `i`.
+#[inline(always)]
+fn word_index(i: usize) -> usize {
+    match i / 16 {
+        0 => i,
+        1 => (5 * i + 1) % 16,
+        2 => (3 * i + 5) % 16,
+        _ => (7 * i) % 16,
+    }
+}
+
+/// One MD5 step in every lane: `b + ((a + f(b, c, d) + k +
+/// m) <<< s)`, the new value of `a`.
+#[inline(always)]
+#[allow(clippy::too_many_arguments)]
+fn step<const N: usize, F>(
+    f: &F,
+    a: Lanes<N>,
+    b: Lanes<N>,
+    c: Lanes<N>,
+    d: Lanes<N>,
+    m: &Lanes<N>,
+    k: u32,
+    s: u32,
+) -> Lanes<N>
+where
+    F: Fn(u32, u32, u32) -> u32,
+{
+    let mut next = [0; N];
+    for l in 0..N {
+        let t = a[l]
+            .wrapping_add(f(b[l], c[l], d[l]))
+            .wrapping_add(k)
+            .wrapping_add(m[l]);
+        next[l] = b[l].wrapping_add(t.rotate_left(s));
+    }
+    next
+}
+
+/// Run the 16 steps of round `R`, with round function `f`,
+/// over a block in every lane.
+#[inline(always)]
+fn round<const N: usize, const R: usize, F>(
+    state: [Lanes<N>; 4],
+    block: &[Lanes<N>; 16],
+    f: F,
+) -> [Lanes<N>; 4]
+where
+    F: Fn(u32, u32, u32) -> u32,
+{
+    let [mut a, mut b, mut c, mut d] = state;
+    let [s0, s1, s2, s3] = S[R];
+    // Each group of four steps cycles the registers back
+    // to their starting roles.
+    for i in (16 * R..16 * (R + 1)).step_by(4) {
+        a = step(&f, a, b, c, d, &block[word_index(i)], K[i], s0);
+        d = step(&f, d, a, b, c, &block[word_index(i + 1)], K[i + 1], s1);
+        c = step(&f, c, d, a, b, &block[word_index(i + 2)], K[i + 2], s2);
+        b = step(&f, b, c, d, a, &block[word_index(i + 3)], K[i + 3], s3);
+    }
+    [a, b, c, d]
+}
+
+/// Run the MD5 compression function on one block in every
+/// lane.
+fn compress<const N: usize>(state: &mut [Lanes<N>; 4], block: &[Lanes<N>; 16]) {
+    let mut work = *state;
+    work = round::<N, 0, _>(work, block, |b, c, d| (b & c) | (!b & d));
+    work = round::<N, 1, _>(work, block, |b, c, d| (b & d) | (c & !d));
+    work = round::<N, 2, _>(work, block, |b, c, d| b ^ c ^ d);
+    work = round::<N, 3, _>(work, block, |b, c, d| c ^ (b | !d));
+    for (reg, new) in state.iter_mut().zip(work) {
+        for l in 0..N {
+            reg[l] = reg[l].wrapping_add(new[l]);
+        }
+    }
+}
+
+/// Number of 64-byte blocks in the padded form of a
+/// message of `len` bytes.
+fn nblocks(len: usize) -> usize {
+    (len + 8) / 64 + 1
+}
+
+/// Bytes of block `j` of the padded form of `message`.
+fn padded_block(message: &[u8], j: usize) -> [u8; 64] {
+    let mut block = [0; 64];
+    let start = 64 * j;
+    if start < message.len() {
+        let tail = &message[start..message.len().min(start + 64)];
+        block[..tail.len()].copy_from_slice(tail);
+    }
+    if (start..start + 64).contains(&message.len()) {
+        block[message.len() - start] = 0x80;
+    }
+    if j + 1 == nblocks(message.len()) {
+        let nbits = (message.len() as u64).wrapping_mul(8);
+        block[56..].copy_from_slice(&nbits.to_le_bytes());
+    }
+    block
+}
+
+/// MD5 digests of `N` messages, hashed side by side.
+pub fn md5_lanes<const N: usize>(messages: [&[u8]; N]) -> [Md5Digest; N] {
+    let mut state = INIT.map(|r| [r; N]);
+    let lane_blocks = messages.map(|m| nblocks(m.len()));
+    let max_blocks = lane_blocks.iter().copied().max().unwrap_or(0);
+    for j in 0..max_blocks {
+        // Transpose the lanes' blocks into words.
+        let mut block = [[0; N]; 16];
+        for (l, message) in messages.iter().enumerate() {
+            let bytes = padded_block(message, j);
+            for (w, word) in bytes.chunks_exact(4).enumerate() {
+                block[w][l] = u32::from_le_bytes(word.try_into().unwrap());
+            }
+        }
+
+        // Lanes whose messages have already ended keep
+        // their old state.
+        let before = state;
+        compress(&mut state, &block);
+        for (l, &n) in lane_blocks.iter().enumerate() {
+            if j >= n {
+                for (reg, old) in state.iter_mut().zip(before.iter()) {
+                    reg[l] = old[l];
+                }
+            }
+        }
+    }
+
+    let mut digests = [[0; 16]; N];
+    for (l, digest) in digests.iter_mut().enumerate() {
+        for (r, reg) in state.iter().enumerate() {
+            digest[4 * r..4 * r + 4].copy_from_slice(&reg[l].to_le_bytes());
+        }
+    }
+    digests
+}
+
+/// MD5 digests of 4 messages.
+pub fn md5x4(messages: [&[u8]; 4]) -> [Md5Digest; 4] {
+    md5_lanes(messages)
+}
+
+/// MD5 digests of 8 messages.
+pub fn md5x8(messages: [&[u8]; 8]) -> [Md5Digest; 8] {
+    md5_lanes(messages)
+}
+
+/// MD5 digests of `prefix` followed by the decimal digits
+/// of each of the `N` indices starting at `start`.
+pub fn md5_indices<const N: usize>(prefix: &[u8], start: usize) -> [Md5Digest; N] {
+    // Build short messages on the stack.
+    const SHORT: usize = 64;
+    let mut digits = [0; 20];
+    if prefix.len() + digits.len() <= SHORT {
+        let mut buffers = [[0; SHORT]; N];
+        let mut lens = [0; N];
+        for (l, (buffer, len)) in buffers.iter_mut().zip(lens.iter_mut()).enumerate() {
+            let index = decimal(start + l, &mut digits);
+            *len = prefix.len() + index.len();
+            buffer[..prefix.len()].copy_from_slice(prefix);
+            buffer[prefix.len()..*len].copy_from_slice(index);
+        }
+        return md5_lanes(std::array::from_fn(|l| &buffers[l][..lens[l]]));
+    }
+
+    let messages: [Vec<u8>; N] = std::array::from_fn(|l| {
+        let mut message = prefix.to_vec();
+        message.extend_from_slice(decimal(start + l, &mut digits));
+        message
+    });
+    md5_lanes(messages.each_ref().map(|m| m.as_slice()))
+}
+
+/// Call `f` with each index in `indices` and the MD5
+/// digest of `prefix` followed by the decimal digits of the
+/// index, in index order, hashing 8 indices at a time.
+pub(crate) fn md5_scan<F>(prefix: &[u8], indices: Range<usize>, mut f: F)
+where
+    F: FnMut(usize, &Md5Digest),
+{
+    let mut start = indices.start;
+    while indices.end.saturating_sub(start) >= 8 {
+        for (l, digest) in md5_indices::<8>(prefix, start).iter().enumerate() {
+            f(start + l, digest);
+        }
+        start += 8;
+    }
+    for i in start..indices.end {
+        let [digest] = md5_indices::<1>(prefix, i);
+        f(i, &digest);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::puzzlehash::{Md5, PuzzleHasher};
+    use rand::prelude::*;
+
+    #[test]
+    fn test_rfc1321() {
+        let suite: [(&[u8], &str); 7] = [
+            (b"", "d41d8cd98f00b204e9800998ecf8427e"),
+            (b"a", "0cc175b9c0f1b6a831c399e269772661"),
+            (b"abc", "900150983cd24fb0d6963f7d28e17f72"),
+            (b"message digest", "f96b697d7cb7938d525a2f31aaf161d0"),
+            (
+                b"abcdefghijklmnopqrstuvwxyz",
+                "c3fcd3d76192e4007dfb496cca67e13b",
+            ),
+            (
+                b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789",
+                "d174ab98d277d9f5a5611c2c9f419d9f",
+            ),
+            (
+                b"12345678901234567890123456789012345678901234567890123456789012345678901234567890",
+                "57edf4a22be3c955ac49da2e2107b67a",
+            ),
+        ];
+        let digests = md5_lanes(suite.map(|(m, _)| m));
+        for ((_, expected), digest) in suite.iter().zip(digests) {
+            assert_eq!(crate::hex_string(&digest), *expected);
+        }
+    }
+
+    #[test]
+    fn test_random_lengths() {
+        // Lengths around the block and padding boundaries
+        // are the interesting ones.
+        let mut seed = [0; 32];
+        seed[0..4].copy_from_slice(&[0x5d, 0x41, 0x40, 0x2a]);
+        let mut rng = StdRng::from_seed(seed);
+        for _ in 0..200 {
+            let messages: [Vec<u8>; 8] = std::array::from_fn(|_| {
+                let len = match rng.gen_range(0..3) {
+                    0 => rng.gen_range(52..70),
+                    1 => rng.gen_range(116..134),
+                    _ => rng.gen_range(0..300),
+                };
+                (0..len).map(|_| rng.gen()).collect()
+            });
+            let refs = messages.each_ref().map(|m| m.as_slice());
+            let expected = refs.map(Md5::digest);
+            assert_eq!(md5x8(refs), expected);
+            let [a, b, c, d, ..] = refs;
+            assert_eq!(md5x4([a, b, c, d]), expected[..4]);
+            assert_eq!(md5_lanes([a]), expected[..1]);
+        }
+    }
+
+    #[test]
+    fn test_indices() {
+        let hasher = crate::SeededHasher::new(b"ngcjuoqr");
+        for start in [0, 7, 99_995, 1 << 40] {
+            let digests = md5_indices::<8>(b"ngcjuoqr", start);
+            for (l, digest) in digests.iter().enumerate() {
+                assert_eq!(*digest, hasher.hash_index(start + l));
+            }
+        }
+
+        // A prefix too long for the short-message buffers.
+        let prefix = [b'x'; 50];
+        let hasher = crate::SeededHasher::new(&prefix);
+        let digests = md5_indices::<4>(&prefix, 123);
+        for (l, digest) in digests.iter().enumerate() {
+            assert_eq!(*digest, hasher.hash_index(123 + l));
+        }
+    }
+
+    #[test]
+    fn test_scan() {
+        let hasher = crate::SeededHasher::new(b"abc");
+        for indices in [0..0, 5..9, 17..20, 99_990..100_013] {
+            let mut seen = Vec::new();
+            md5_scan(b"abc", indices.clone(), |i, digest| {
+                assert_eq!(*digest, hasher.hash_index(i));
+                seen.push(i);
+            });
+            assert!(seen.into_iter().eq(indices));
+        }
+    }
+}
@@ -14,6 +14,10 @@
 // For an explanation of the structure of this file, see
 // http://stackoverflow.com/questions/22596920/
 
+// Code generated by `#[derive(FromLine)]` names this crate
+// `aoc`, including in its own tests.
+extern crate self as aoc;
+
 pub mod args;
 pub use self::args::*;
 
@@ -23,17 +27,44 @@ pub use self::sets::*;
 pub mod bits;
 pub use self::bits::*;
 
+pub mod bitset;
+pub use self::bitset::*;
+
+pub mod bitrow;
+pub use self::bitrow::*;
+
+pub mod cycle;
+pub use self::cycle::*;
+
 pub mod lines;
 pub use self::lines::*;
 
+pub mod input;
+pub use self::input::*;
+
+pub mod lineparse;
+pub use self::lineparse::*;
+
 pub mod hex;
 pub use self::hex::*;
 
+pub mod puzzlehash;
+pub use self::puzzlehash::*;
+
+pub mod md5lanes;
+pub use self::md5lanes::*;
+
+pub mod hashsearch;
+pub use self::hashsearch::*;
+
 pub mod astar;
 pub use self::astar::*;
 
 pub mod dirns;
 pub use self::dirns::*;
 
+pub mod maze;
+pub use self::maze::*;
+
 pub mod asm;
 pub use self::asm::*;
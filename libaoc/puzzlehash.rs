@@ -0,0 +1,316 @@
+// Copyright © 2016 Bart Massey
+// This program is licensed under the "MIT License".
+// Please see the file COPYING in this distribution
+// for license terms.
+
+//! Hash functions for the hash-based puzzles.
+//!
+//! The hash-based puzzles all use MD5, but nothing about
+//! them depends on it. `PuzzleHasher` is the little bit of
+//! interface they need: start, feed bytes, finish. It is
+//! implemented for MD5, SHA-1 and SHA-256, so that puzzle
+//! variants can use those, and for `FastHash`, a cheap
+//! non-cryptographic hash for unit tests that want to
+//! exercise the logic around the hashing without paying for
+//! the real thing.
+//!
+//! `HashKind` names the available hashes, so that solutions
+//! can pick one at run time: `HashKind::from_env()` reads
+//! the `AOC_HASH` environment variable, which may be `md5`
+//! (the default), `sha1`, `sha256` or `fast`. The
+//! `with_hasher!` macro then calls a function generic in
+//! the hash with the chosen one.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use aoc::{FastHash, Md5, PuzzleHasher, Sha1, Sha256};
+//!
+//! assert_eq!(aoc::hex_string(&Md5::digest(b"abc")), "900150983cd24fb0d6963f7d28e17f72");
+//! assert_eq!(Sha1::digest(b"abc").len(), 20);
+//! assert_eq!(Sha256::digest(b"abc").len(), 32);
+//! assert_ne!(FastHash::digest(b"abc"), FastHash::digest(b"abd"));
+//!
+//! fn digest_len<H: PuzzleHasher>(bytes: &[u8]) -> usize {
+//!     H::digest(bytes).as_ref().len()
+//! }
+//!
+//! let kind: aoc::HashKind = "sha1".parse().unwrap();
+//! assert_eq!(aoc::with_hasher!(kind, digest_len(b"abc")), 20);
+//! ```
+
+use std::fmt;
+use std::ops::Range;
+use std::str::FromStr;
+
+use crate::hashsearch::decimal;
+
+pub use md5::Md5;
+pub use sha1::Sha1;
+pub use sha2::Sha256;
+
+/// A hash function as the puzzles use it.
+pub trait PuzzleHasher: Clone + Send + Sync + 'static {
+    /// The digest.
+    type Output: AsRef<[u8]> + Copy + Eq + fmt::Debug + Send + Sync + 'static;
+
+    /// A hasher that has seen no input.
+    fn new() -> Self;
+
+    /// Feed `bytes` to the hasher.
+    fn update(&mut self, bytes: &[u8]);
+
+    /// Finish hashing and return the digest.
+    fn finalize(self) -> Self::Output;
+
+    /// Digest of `bytes`.
+    fn digest(bytes: &[u8]) -> Self::Output {
+        let mut hasher = Self::new();
+        hasher.update(bytes);
+        hasher.finalize()
+    }
+
+    /// Call `f` with each index in `indices` and the digest
+    /// of `prefix` followed by the decimal digits of the
+    /// index, in index order. `seeded` has already been fed
+    /// `prefix`. Hashers that can hash several messages at
+    /// once override this.
+    fn hash_indices<F>(seeded: &Self, _prefix: &[u8], indices: Range<usize>, mut f: F)
+    where
+        F: FnMut(usize, &Self::Output),
+    {
+        let mut buf = [0; 20];
+        for i in indices {
+            let mut hasher = seeded.clone();
+            hasher.update(decimal(i, &mut buf));
+            f(i, &hasher.finalize());
+        }
+    }
+}
+
+/// Implement `PuzzleHasher` for a RustCrypto hasher with
+/// the given `Digest` trait and digest size, plus any
+/// extra items.
+macro_rules! rustcrypto_hasher {
+    ($hasher:ty, $digest:path, $size:expr $(, { $($extra:tt)* })?) => {
+        impl PuzzleHasher for $hasher {
+            type Output = [u8; $size];
+
+            fn new() -> Self {
+                <Self as $digest>::new()
+            }
+
+            fn update(&mut self, bytes: &[u8]) {
+                <Self as $digest>::update(self, bytes);
+            }
+
+            fn finalize(self) -> [u8; $size] {
+                <Self as $digest>::finalize(self).into()
+            }
+
+            $($($extra)*)?
+        }
+    };
+}
+
+rustcrypto_hasher!(Md5, md5::Digest, 16, {
+    fn hash_indices<F>(_seeded: &Self, prefix: &[u8], indices: Range<usize>, f: F)
+    where
+        F: FnMut(usize, &[u8; 16]),
+    {
+        crate::md5lanes::md5_scan(prefix, indices, f);
+    }
+});
+rustcrypto_hasher!(Sha1, sha1::Digest, 20);
+rustcrypto_hasher!(Sha256, sha2::Digest, 32);
+
+/// Cheap non-cryptographic hash with a 16-byte digest, for
+/// tests. Two differently-seeded FNV-1a lanes, each mixed
+/// at the end.
+#[derive(Clone, Debug)]
+pub struct FastHash {
+    /// First lane.
+    a: u64,
+    /// Second lane.
+    b: u64,
+    /// Number of bytes seen.
+    len: u64,
+}
+
+/// Final avalanche for a `FastHash` lane (from SplitMix64).
+fn mix64(mut x: u64) -> u64 {
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d049bb133111eb);
+    x ^ (x >> 31)
+}
+
+impl PuzzleHasher for FastHash {
+    type Output = [u8; 16];
+
+    fn new() -> Self {
+        FastHash {
+            a: 0xcbf29ce484222325,
+            b: 0x84222325cbf29ce4,
+            len: 0,
+        }
+    }
+
+    fn update(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.a = (self.a ^ byte as u64).wrapping_mul(0x100000001b3);
+            self.b = (self.b ^ byte as u64).wrapping_mul(0x9e3779b97f4a7c15);
+        }
+        self.len += bytes.len() as u64;
+    }
+
+    fn finalize(self) -> [u8; 16] {
+        let a = mix64(self.a ^ self.len);
+        let b = mix64(self.b ^ a);
+        let mut digest = [0; 16];
+        digest[..8].copy_from_slice(&a.to_be_bytes());
+        digest[8..].copy_from_slice(&b.to_be_bytes());
+        digest
+    }
+}
+
+/// The available puzzle hashes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HashKind {
+    /// `Md5`: what the puzzles use.
+    Md5,
+    /// `Sha1`.
+    Sha1,
+    /// `Sha256`.
+    Sha256,
+    /// `FastHash`.
+    Fast,
+}
+
+impl HashKind {
+    /// The hash named by the `AOC_HASH` environment
+    /// variable, or MD5 if it is not set.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `AOC_HASH` names no known hash.
+    pub fn from_env() -> HashKind {
+        match std::env::var("AOC_HASH") {
+            Ok(name) => name.parse().unwrap_or_else(|e| panic!("AOC_HASH: {}", e)),
+            Err(_) => HashKind::Md5,
+        }
+    }
+}
+
+/// Call the function `f`, generic in a `PuzzleHasher`, as
+/// `f::<H>(args...)` with `H` the hash named by the given
+/// `HashKind`.
+#[macro_export]
+macro_rules! with_hasher {
+    ($kind:expr, $f:ident($($arg:expr),* $(,)?)) => {
+        match $kind {
+            $crate::HashKind::Md5 => $f::<$crate::Md5>($($arg),*),
+            $crate::HashKind::Sha1 => $f::<$crate::Sha1>($($arg),*),
+            $crate::HashKind::Sha256 => $f::<$crate::Sha256>($($arg),*),
+            $crate::HashKind::Fast => $f::<$crate::FastHash>($($arg),*),
+        }
+    };
+}
+
+/// Error from parsing an unknown hash name.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UnknownHash(pub String);
+
+impl fmt::Display for UnknownHash {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "unknown hash {:?}: expected md5, sha1, sha256 or fast",
+            self.0
+        )
+    }
+}
+
+impl std::error::Error for UnknownHash {}
+
+impl FromStr for HashKind {
+    type Err = UnknownHash;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "md5" => Ok(HashKind::Md5),
+            "sha1" => Ok(HashKind::Sha1),
+            "sha256" => Ok(HashKind::Sha256),
+            "fast" => Ok(HashKind::Fast),
+            _ => Err(UnknownHash(s.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Hash `bytes` in two pieces split at `split`.
+    fn split_digest<H: PuzzleHasher>(bytes: &[u8], split: usize) -> H::Output {
+        let mut hasher = H::new();
+        hasher.update(&bytes[..split]);
+        hasher.update(&bytes[split..]);
+        hasher.finalize()
+    }
+
+    #[test]
+    fn test_known_digests() {
+        let hex = |d: &[u8]| crate::hex_string(d);
+        assert_eq!(hex(&Md5::digest(b"")), "d41d8cd98f00b204e9800998ecf8427e");
+        assert_eq!(
+            hex(&Sha1::digest(b"abc")),
+            "a9993e364706816aba3e25717850c26c9cd0d89d"
+        );
+        assert_eq!(
+            hex(&Sha256::digest(b"abc")),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[test]
+    fn test_incremental() {
+        let text = b"The quick brown fox jumps over the lazy dog";
+        for split in [0, 1, 17, text.len()] {
+            assert_eq!(split_digest::<Md5>(text, split), Md5::digest(text));
+            assert_eq!(split_digest::<Sha1>(text, split), Sha1::digest(text));
+            assert_eq!(split_digest::<Sha256>(text, split), Sha256::digest(text));
+            assert_eq!(
+                split_digest::<FastHash>(text, split),
+                FastHash::digest(text)
+            );
+        }
+    }
+
+    #[test]
+    fn test_fast_hash_spread() {
+        // Each nybble position should see every value
+        // roughly equally often.
+        let mut counts = [[0usize; 16]; 32];
+        for i in 0..16000 {
+            let digest = FastHash::digest(i.to_string().as_bytes());
+            for (posn, d) in crate::nybbles(&digest).enumerate() {
+                counts[posn][d as usize] += 1;
+            }
+        }
+        for posn in counts.iter() {
+            for &count in posn.iter() {
+                assert!((800..1200).contains(&count), "{:?}", posn);
+            }
+        }
+    }
+
+    #[test]
+    fn test_hash_kind() {
+        assert_eq!("sha256".parse(), Ok(HashKind::Sha256));
+        assert_eq!("fast".parse(), Ok(HashKind::Fast));
+        assert_eq!(
+            "crc".parse::<HashKind>(),
+            Err(UnknownHash("crc".to_string()))
+        );
+    }
+}
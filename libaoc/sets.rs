@@ -5,36 +5,183 @@
 
 //! Set operations for Advent of Code 2016 solutions.
 //!
-//! These are specifically `std::collections::BTreeSet`
-//! operations, because `std::collections::HashSet` is not
-//! hashable (why?) and thus cannot be contained in a
-//! `HashSet`.
+//! The `Set` trait parameterizes the stuff that doesn't
+//! care about set representation. It is implemented for
+//! `std::collections::BTreeSet`,
+//! `std::collections::HashSet`, `aoc::BitSet64` and
+//! `aoc::BitSet`.
 //!
-//! It would nice to have a generic `std::collections::Set`
-//! trait to parameterize the stuff that doesn't care.
+//! Sets of sets are specifically `BTreeSet`s, because
+//! `std::collections::HashSet` is not hashable (why?) and
+//! thus cannot be contained in a `HashSet`. For the same
+//! reason, `choose()` and `choose_le()` do not work on
+//! `HashSet`s.
+//!
+//! `choose()` and `choose_le()` build their entire result
+//! eagerly. The iterators `combinations()`,
+//! `combinations_le()`, `permutations()` and `product()`
+//! instead produce their results lazily, one `Vec` at a
+//! time, in lexicographic order of source position.
+//!
+//! # Examples
+//!
+//! ```rust
+//! let grabs = aoc::combinations_le(&['a', 'b', 'c'], 2).collect::<Vec<_>>();
+//! assert_eq!(grabs.len(), 7);
+//! assert_eq!(grabs[0], vec![]);
+//! assert_eq!(grabs[6], vec!['b', 'c']);
+//! ```
 
-use std::collections::BTreeSet;
+use std::collections::{btree_set, hash_set, BTreeSet, HashSet};
+use std::hash::{BuildHasher, Hash};
+use std::iter::Cloned;
 
-/// Make a set from a slice.
-pub fn make_set<T>(elems: &[T]) -> BTreeSet<T>
+/// Operations common to set representations.
+///
+/// `BTreeSet` and `HashSet` have inherent methods with some
+/// of these names that take precedence in method-call
+/// syntax: use `Set::union(&a, &b)` and the like when the
+/// set type is concrete.
+pub trait Set: Clone + Default {
+    /// Type of set elements.
+    type Elem: Clone;
+
+    /// Iterator over set elements, by value.
+    type Iter<'a>: Iterator<Item = Self::Elem>
+    where
+        Self: 'a;
+
+    /// Add an element, returning true iff it was not
+    /// already present.
+    fn insert(&mut self, elem: Self::Elem) -> bool;
+
+    /// Remove an element, returning true iff it was
+    /// present.
+    fn remove(&mut self, elem: &Self::Elem) -> bool;
+
+    /// Return true iff the element is present.
+    fn contains(&self, elem: &Self::Elem) -> bool;
+
+    /// Number of elements.
+    fn len(&self) -> usize;
+
+    /// Return an iterator over the elements.
+    fn elems(&self) -> Self::Iter<'_>;
+
+    /// Return true iff there are no elements.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Make a set from the given elements.
+    fn from_elems<I>(elems: I) -> Self
+    where
+        I: IntoIterator<Item = Self::Elem>,
+    {
+        let mut s = Self::default();
+        for e in elems {
+            s.insert(e);
+        }
+        s
+    }
+
+    /// Elements in either set.
+    fn union(&self, other: &Self) -> Self {
+        let mut s = self.clone();
+        for e in other.elems() {
+            s.insert(e);
+        }
+        s
+    }
+
+    /// Elements in this set but not the other.
+    fn difference(&self, other: &Self) -> Self {
+        Self::from_elems(self.elems().filter(|e| !other.contains(e)))
+    }
+
+    /// Elements in both sets.
+    fn intersection(&self, other: &Self) -> Self {
+        Self::from_elems(self.elems().filter(|e| other.contains(e)))
+    }
+
+    /// Return true iff every element of this set is in the
+    /// other.
+    fn is_subset(&self, other: &Self) -> bool {
+        self.len() <= other.len() && self.elems().all(|e| other.contains(&e))
+    }
+}
+
+impl<T: Clone + Ord> Set for BTreeSet<T> {
+    type Elem = T;
+    type Iter<'a>
+        = Cloned<btree_set::Iter<'a, T>>
+    where
+        T: 'a;
+
+    fn insert(&mut self, elem: T) -> bool {
+        BTreeSet::insert(self, elem)
+    }
+
+    fn remove(&mut self, elem: &T) -> bool {
+        BTreeSet::remove(self, elem)
+    }
+
+    fn contains(&self, elem: &T) -> bool {
+        BTreeSet::contains(self, elem)
+    }
+
+    fn len(&self) -> usize {
+        BTreeSet::len(self)
+    }
+
+    fn elems(&self) -> Self::Iter<'_> {
+        self.iter().cloned()
+    }
+}
+
+impl<T, H> Set for HashSet<T, H>
 where
-    T: Clone + Ord,
+    T: Clone + Eq + Hash,
+    H: BuildHasher + Clone + Default,
 {
-    elems.iter().cloned().collect()
+    type Elem = T;
+    type Iter<'a>
+        = Cloned<hash_set::Iter<'a, T>>
+    where
+        T: 'a,
+        H: 'a;
+
+    fn insert(&mut self, elem: T) -> bool {
+        HashSet::insert(self, elem)
+    }
+
+    fn remove(&mut self, elem: &T) -> bool {
+        HashSet::remove(self, elem)
+    }
+
+    fn contains(&self, elem: &T) -> bool {
+        HashSet::contains(self, elem)
+    }
+
+    fn len(&self) -> usize {
+        HashSet::len(self)
+    }
+
+    fn elems(&self) -> Self::Iter<'_> {
+        self.iter().cloned()
+    }
+}
+
+/// Make a set from a slice.
+pub fn make_set<S: Set>(elems: &[S::Elem]) -> S {
+    S::from_elems(elems.iter().cloned())
 }
-/*
-    let mut s = BTreeSet::new();
-    for v in elems {
-        s.insert((*v).clone());
-    };
-    s
-*/
 
 /// Consruct the set of all choices of `n` items from a given
 /// set of items.
-pub fn choose<T>(source: &BTreeSet<T>, n: usize) -> BTreeSet<BTreeSet<T>>
+pub fn choose<S>(source: &S, n: usize) -> BTreeSet<S>
 where
-    T: Clone + Ord,
+    S: Set + Ord,
 {
     if source.len() < n {
         let empty = BTreeSet::new();
@@ -42,13 +189,13 @@ where
     };
     let mut r = BTreeSet::new();
     if n == 0 {
-        let empty = BTreeSet::new();
+        let empty = S::default();
         r.insert(empty);
         return r;
     };
     let mut es = source.clone();
-    for e in source.iter() {
-        es.remove(e);
+    for e in source.elems() {
+        es.remove(&e);
         let cs = *Box::new(choose(&es, n - 1));
         for mut c in cs {
             c.insert(e.clone());
@@ -60,9 +207,9 @@ where
 
 /// Consruct the set of all choices of `0..n` items from a given
 /// set of items.
-pub fn choose_le<T>(source: &BTreeSet<T>, n: usize) -> BTreeSet<BTreeSet<T>>
+pub fn choose_le<S>(source: &S, n: usize) -> BTreeSet<S>
 where
-    T: Clone + Ord,
+    S: Set + Ord,
 {
     let mut r = choose(source, 0);
     for i in 1..n + 1 {
@@ -72,6 +219,215 @@ where
     r
 }
 
+/// Iterator over the `k`-element combinations of a
+/// sequence. See `combinations()`.
+#[derive(Clone, Debug)]
+pub struct Combinations<T> {
+    /// Source items.
+    items: Vec<T>,
+    /// Positions of the next combination, if any.
+    indices: Option<Vec<usize>>,
+}
+
+/// Return an iterator over the `k`-element combinations of
+/// the given items, each as a `Vec` in source order.
+pub fn combinations<T: Clone>(items: &[T], k: usize) -> Combinations<T> {
+    Combinations::new(items.to_vec(), k)
+}
+
+/// Return an iterator over the `k`-element subsets of the
+/// given set, each as a `Vec` in set iteration order. This
+/// produces the same subsets as `choose()`.
+pub fn set_combinations<S: Set>(source: &S, k: usize) -> Combinations<S::Elem> {
+    Combinations::new(source.elems().collect(), k)
+}
+
+impl<T: Clone> Combinations<T> {
+    /// Iterate over the `k`-combinations of the given
+    /// items.
+    fn new(items: Vec<T>, k: usize) -> Self {
+        let indices = if k <= items.len() {
+            Some((0..k).collect())
+        } else {
+            None
+        };
+        Combinations { items, indices }
+    }
+}
+
+impl<T: Clone> Iterator for Combinations<T> {
+    type Item = Vec<T>;
+
+    fn next(&mut self) -> Option<Vec<T>> {
+        let indices = self.indices.as_mut()?;
+        let result = indices.iter().map(|&i| self.items[i].clone()).collect();
+
+        // Advance the rightmost position that has room,
+        // then pack the positions after it.
+        let n = self.items.len();
+        let k = indices.len();
+        match (0..k).rev().find(|&i| indices[i] < n - k + i) {
+            Some(i) => {
+                indices[i] += 1;
+                for j in i + 1..k {
+                    indices[j] = indices[j - 1] + 1;
+                }
+            }
+            None => self.indices = None,
+        }
+        Some(result)
+    }
+}
+
+/// Iterator over the combinations of at most `k` elements
+/// of a sequence. See `combinations_le()`.
+#[derive(Clone, Debug)]
+pub struct CombinationsLe<T> {
+    /// Combinations of the current size.
+    current: Combinations<T>,
+    /// Current size.
+    size: usize,
+    /// Largest size to produce.
+    k: usize,
+}
+
+/// Return an iterator over the combinations of `0..=k`
+/// elements of the given items, by increasing size.
+pub fn combinations_le<T: Clone>(items: &[T], k: usize) -> CombinationsLe<T> {
+    CombinationsLe {
+        current: combinations(items, 0),
+        size: 0,
+        k,
+    }
+}
+
+/// Return an iterator over the subsets of at most `k`
+/// elements of the given set, by increasing size. This
+/// produces the same subsets as `choose_le()`.
+pub fn set_combinations_le<S: Set>(source: &S, k: usize) -> CombinationsLe<S::Elem> {
+    CombinationsLe {
+        current: set_combinations(source, 0),
+        size: 0,
+        k,
+    }
+}
+
+impl<T: Clone> Iterator for CombinationsLe<T> {
+    type Item = Vec<T>;
+
+    fn next(&mut self) -> Option<Vec<T>> {
+        loop {
+            if let Some(c) = self.current.next() {
+                return Some(c);
+            }
+            if self.size >= self.k || self.size >= self.current.items.len() {
+                return None;
+            }
+            self.size += 1;
+            let items = std::mem::take(&mut self.current.items);
+            self.current = Combinations::new(items, self.size);
+        }
+    }
+}
+
+/// Iterator over the permutations of a sequence. See
+/// `permutations()`.
+#[derive(Clone, Debug)]
+pub struct Permutations<T> {
+    /// Source items.
+    items: Vec<T>,
+    /// Positions of the next permutation, if any.
+    indices: Option<Vec<usize>>,
+}
+
+/// Return an iterator over all orderings of the given
+/// items, in lexicographic order of source position.
+pub fn permutations<T: Clone>(items: &[T]) -> Permutations<T> {
+    Permutations {
+        items: items.to_vec(),
+        indices: Some((0..items.len()).collect()),
+    }
+}
+
+impl<T: Clone> Iterator for Permutations<T> {
+    type Item = Vec<T>;
+
+    fn next(&mut self) -> Option<Vec<T>> {
+        let indices = self.indices.as_mut()?;
+        let result = indices.iter().map(|&i| self.items[i].clone()).collect();
+
+        // Standard next-permutation step.
+        let n = indices.len();
+        match (1..n).rev().find(|&i| indices[i - 1] < indices[i]) {
+            Some(i) => {
+                let j = (i..n)
+                    .rev()
+                    .find(|&j| indices[j] > indices[i - 1])
+                    .expect("permutations: no successor");
+                indices.swap(i - 1, j);
+                indices[i..].reverse();
+            }
+            None => self.indices = None,
+        }
+        Some(result)
+    }
+}
+
+/// Iterator over the Cartesian product of some sequences.
+/// See `product()`.
+#[derive(Clone, Debug)]
+pub struct Product<T> {
+    /// Source sequences.
+    factors: Vec<Vec<T>>,
+    /// Positions of the next tuple, if any.
+    indices: Option<Vec<usize>>,
+}
+
+/// Return an iterator over the Cartesian product of the
+/// given sequences: every `Vec` whose `i`th element is
+/// taken from `factors[i]`, with the last position varying
+/// fastest.
+pub fn product<T: Clone>(factors: &[&[T]]) -> Product<T> {
+    let indices = if factors.iter().all(|f| !f.is_empty()) {
+        Some(vec![0; factors.len()])
+    } else {
+        None
+    };
+    Product {
+        factors: factors.iter().map(|f| f.to_vec()).collect(),
+        indices,
+    }
+}
+
+impl<T: Clone> Iterator for Product<T> {
+    type Item = Vec<T>;
+
+    fn next(&mut self) -> Option<Vec<T>> {
+        let indices = self.indices.as_mut()?;
+        let result = indices
+            .iter()
+            .zip(self.factors.iter())
+            .map(|(&i, f)| f[i].clone())
+            .collect();
+
+        // Odometer step.
+        let mut i = indices.len();
+        loop {
+            if i == 0 {
+                self.indices = None;
+                break;
+            }
+            i -= 1;
+            indices[i] += 1;
+            if indices[i] < self.factors[i].len() {
+                break;
+            }
+            indices[i] = 0;
+        }
+        Some(result)
+    }
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -94,7 +450,7 @@ mod tests {
 
     #[test]
     fn choose_zero() {
-        let s = make_set(&[1, 2, 3]);
+        let s: BTreeSet<_> = make_set(&[1, 2, 3]);
         let cs = choose(&s, 0);
         let mut xs = BTreeSet::new();
         xs.insert(BTreeSet::new());
@@ -103,7 +459,7 @@ mod tests {
 
     #[test]
     fn choose_too_many() {
-        let s = make_set(&[1, 2, 3]);
+        let s: BTreeSet<_> = make_set(&[1, 2, 3]);
         let cs = choose(&s, 4);
         let xs = BTreeSet::new();
         assert!(cs == xs);
@@ -111,7 +467,7 @@ mod tests {
 
     #[test]
     fn three_choose_one() {
-        let s = make_set(&[1, 2, 3]);
+        let s: BTreeSet<_> = make_set(&[1, 2, 3]);
         let cs = choose(&s, 1);
         let t = make_set_set(&[&[1], &[2], &[3]]);
         assert!(cs == t);
@@ -119,7 +475,7 @@ mod tests {
 
     #[test]
     fn three_choose_two() {
-        let s = make_set(&[1, 2, 3]);
+        let s: BTreeSet<_> = make_set(&[1, 2, 3]);
         let cs = choose(&s, 2);
         let t = make_set_set(&[&[1, 2], &[2, 3], &[1, 3]]);
         assert!(cs == t);
@@ -127,9 +483,101 @@ mod tests {
 
     #[test]
     fn four_choose_two() {
-        let s = make_set(&[1, 2, 3, 4]);
+        let s: BTreeSet<_> = make_set(&[1, 2, 3, 4]);
         let cs = choose(&s, 2);
         let t = make_set_set(&[&[1, 2], &[1, 3], &[1, 4], &[2, 3], &[2, 4], &[3, 4]]);
         assert!(cs == t);
     }
+
+    /// Collect lazily-generated subsets into a set of sets.
+    fn collect_set_set<T, I>(vs: I) -> BTreeSet<BTreeSet<T>>
+    where
+        T: Ord,
+        I: Iterator<Item = Vec<T>>,
+    {
+        vs.map(|v| v.into_iter().collect()).collect()
+    }
+
+    #[test]
+    fn combinations_match_choose() {
+        let s: BTreeSet<_> = make_set(&[1, 2, 3, 4, 5]);
+        for k in 0..7 {
+            let cs = collect_set_set(set_combinations(&s, k));
+            assert_eq!(cs, choose(&s, k));
+            let cs = collect_set_set(set_combinations_le(&s, k));
+            assert_eq!(cs, choose_le(&s, k));
+        }
+    }
+
+    #[test]
+    fn representations_agree() {
+        let elems = [1, 4, 9, 16, 25, 36];
+        let b: BTreeSet<usize> = make_set(&elems);
+        let h: HashSet<usize> = make_set(&elems);
+        let bits: crate::BitSet64 = make_set(&elems);
+        let others = [4, 5, 16, 60];
+        let b2: BTreeSet<usize> = make_set(&others);
+        let h2: HashSet<usize> = make_set(&others);
+        let bits2: crate::BitSet64 = make_set(&others);
+        let sorted = |s: &Vec<usize>| make_set::<BTreeSet<usize>>(s);
+        for (ub, uh, ubits) in [
+            (Set::union(&b, &b2), Set::union(&h, &h2), bits.union(&bits2)),
+            (
+                Set::difference(&b, &b2),
+                Set::difference(&h, &h2),
+                bits.difference(&bits2),
+            ),
+            (
+                Set::intersection(&b, &b2),
+                Set::intersection(&h, &h2),
+                bits.intersection(&bits2),
+            ),
+        ]
+        .into_iter()
+        .map(|(x, y, z)| {
+            (
+                x.elems().collect(),
+                y.elems().collect(),
+                z.elems().collect(),
+            )
+        }) {
+            assert_eq!(sorted(&ub), sorted(&uh));
+            assert_eq!(sorted(&ub), sorted(&ubits));
+        }
+        let small: crate::BitSet64 = make_set(&[4, 16]);
+        assert!(small.is_subset(&bits) && small.is_subset(&bits2));
+        assert!(!bits.is_subset(&bits2));
+        assert_eq!(choose(&bits, 2).len(), 15);
+        assert_eq!(choose_le(&bits, 6).len(), 64);
+    }
+
+    #[test]
+    fn combinations_count() {
+        let items = [1, 2, 3, 4, 5, 6];
+        assert_eq!(combinations(&items, 3).count(), 20);
+        assert_eq!(combinations_le(&items, 2).count(), 22);
+        assert_eq!(combinations(&items, 7).count(), 0);
+        assert_eq!(combinations_le(&[0u8; 0], 3).count(), 1);
+    }
+
+    #[test]
+    fn permutations_in_order() {
+        let ps = permutations(&['a', 'b', 'c']).collect::<Vec<_>>();
+        assert_eq!(ps.len(), 6);
+        assert_eq!(ps[0], vec!['a', 'b', 'c']);
+        assert_eq!(ps[1], vec!['a', 'c', 'b']);
+        assert_eq!(ps[5], vec!['c', 'b', 'a']);
+        assert_eq!(permutations(&[0u8; 0]).count(), 1);
+    }
+
+    #[test]
+    fn product_odometer() {
+        let ps = product(&[&[1, 2], &[3], &[4, 5]]).collect::<Vec<_>>();
+        assert_eq!(
+            ps,
+            vec![vec![1, 3, 4], vec![1, 3, 5], vec![2, 3, 4], vec![2, 3, 5]]
+        );
+        assert_eq!(product::<u8>(&[&[1], &[]]).count(), 0);
+        assert_eq!(product::<u8>(&[]).count(), 1);
+    }
 }
@@ -1,11 +1,263 @@
+//! Typed access to `regex` capture groups.
+//!
+//! `CapturesAtExt` papers over the `regex` crate's removal
+//! of `Captures::at()`, and adds parsing of capture groups
+//! into typed values: `parse_at()` by group index,
+//! `parse_named()` by group name, and `parse_all()` for all
+//! the groups at once as a tuple. Failures are
+//! `CaptureError`s, which say which group failed and quote
+//! the text the regex matched. A group the regex does not
+//! have is reported as `Missing`, like one that did not
+//! match; `parse_all()` into a tuple of the wrong size is a
+//! bug rather than a failed match, and panics.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use captures_at::CapturesAtExt;
+//!
+//! let pat = regex::Regex::new(r"(?P<bot>\d+) gives (\w+) to (\d+)").unwrap();
+//! let caps = pat.captures("bot 2 gives low to 17").unwrap();
+//! assert_eq!(caps.at(2), Some("low"));
+//! assert_eq!(caps.parse_named::<u32>("bot"), Ok(2));
+//! assert_eq!(caps.parse_at::<u32>(3), Ok(17));
+//! let (bot, which, dest): (u32, String, u32) = caps.parse_all().unwrap();
+//! assert_eq!((bot, which.as_str(), dest), (2, "low", 17));
+//!
+//! let caps = pat.captures("999999999999 gives low to 17").unwrap();
+//! assert_eq!(
+//!     caps.parse_at::<u32>(1).unwrap_err().to_string(),
+//!     "group 1 (\"999999999999\") of \"999999999999 gives low to 17\": \
+//!      number too large to fit in target type",
+//! );
+//! ```
+
+use std::fmt;
+use std::str::FromStr;
+
 use regex::*;
 
+/// A capture group, by index or by name.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Group {
+    /// Numbered group.
+    Index(usize),
+    /// Named group.
+    Name(String),
+}
+
+impl fmt::Display for Group {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Group::Index(n) => write!(f, "group {}", n),
+            Group::Name(name) => write!(f, "group `{}`", name),
+        }
+    }
+}
+
+/// What went wrong with a capture group.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CaptureErrorKind {
+    /// The group did not participate in the match, or the
+    /// regex has no such group.
+    Missing,
+    /// The group's text would not parse.
+    BadValue {
+        /// Text of the group.
+        text: String,
+        /// Why it would not parse.
+        reason: String,
+    },
+}
+
+/// Error from extracting a capture group.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CaptureError {
+    /// The group that failed.
+    pub group: Group,
+    /// The text the whole regex matched.
+    pub source: String,
+    /// What went wrong.
+    pub kind: CaptureErrorKind,
+}
+
+impl fmt::Display for CaptureError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.kind {
+            CaptureErrorKind::Missing => {
+                write!(f, "{} of {:?}: no match", self.group, self.source)
+            }
+            CaptureErrorKind::BadValue { text, reason } => write!(
+                f,
+                "{} ({:?}) of {:?}: {}",
+                self.group, text, self.source, reason
+            ),
+        }
+    }
+}
+
+impl std::error::Error for CaptureError {}
+
+/// Parse `text` matched by `group` of `caps`.
+fn parse_group<T>(caps: &Captures, group: Group, text: Option<&str>) -> Result<T, CaptureError>
+where
+    T: FromStr,
+    T::Err: fmt::Display,
+{
+    let error = |kind| CaptureError {
+        group: group.clone(),
+        source: caps[0].to_string(),
+        kind,
+    };
+    let text = text.ok_or_else(|| error(CaptureErrorKind::Missing))?;
+    text.parse().map_err(|e: T::Err| {
+        error(CaptureErrorKind::BadValue {
+            text: text.to_string(),
+            reason: e.to_string(),
+        })
+    })
+}
+
+/// Types that can be built from all the capture groups of
+/// a match: implemented for tuples of up to six
+/// `FromStr` types, taken from groups 1 on.
+pub trait FromCaptures: Sized {
+    /// Build from `caps`.
+    fn from_captures(caps: &Captures) -> Result<Self, CaptureError>;
+}
+
+/// Implement `FromCaptures` for a tuple type.
+macro_rules! tuple_from_captures {
+    ($n:expr; $($t:ident $i:expr),*) => {
+        impl<$($t),*> FromCaptures for ($($t,)*)
+        where
+            $($t: FromStr, $t::Err: fmt::Display),*
+        {
+            fn from_captures(caps: &Captures) -> Result<Self, CaptureError> {
+                assert_eq!(
+                    caps.len(),
+                    $n + 1,
+                    "regex has {} capture groups, not {}",
+                    caps.len() - 1,
+                    $n,
+                );
+                Ok(($(caps.parse_at::<$t>($i)?,)*))
+            }
+        }
+    };
+}
+
+tuple_from_captures!(1; A 1);
+tuple_from_captures!(2; A 1, B 2);
+tuple_from_captures!(3; A 1, B 2, C 3);
+tuple_from_captures!(4; A 1, B 2, C 3, D 4);
+tuple_from_captures!(5; A 1, B 2, C 3, D 4, E 5);
+tuple_from_captures!(6; A 1, B 2, C 3, D 4, E 5, F 6);
+
+/// Extension methods for `regex::Captures`.
 pub trait CapturesAtExt<'a> {
+    /// Text of group `n`, if it participated in the match.
     fn at(&'a self, n: usize) -> Option<&'a str>;
+
+    /// Parse the text of group `n`.
+    fn parse_at<T>(&'a self, n: usize) -> Result<T, CaptureError>
+    where
+        T: FromStr,
+        T::Err: fmt::Display;
+
+    /// Parse the text of the group named `name`.
+    fn parse_named<T>(&'a self, name: &str) -> Result<T, CaptureError>
+    where
+        T: FromStr,
+        T::Err: fmt::Display;
+
+    /// Parse all the capture groups, in order.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the regex has the wrong number of groups
+    /// for `T`.
+    fn parse_all<T: FromCaptures>(&'a self) -> Result<T, CaptureError>;
 }
 
 impl<'a> CapturesAtExt<'a> for Captures<'a> {
     fn at(&'a self, n: usize) -> Option<&'a str> {
         self.get(n).map(|m| m.as_str())
     }
+
+    fn parse_at<T>(&'a self, n: usize) -> Result<T, CaptureError>
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+    {
+        parse_group(self, Group::Index(n), self.at(n))
+    }
+
+    fn parse_named<T>(&'a self, name: &str) -> Result<T, CaptureError>
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+    {
+        let text = self.name(name).map(|m| m.as_str());
+        parse_group(self, Group::Name(name.to_string()), text)
+    }
+
+    fn parse_all<T: FromCaptures>(&'a self) -> Result<T, CaptureError> {
+        T::from_captures(self)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let pat = Regex::new(r"^(\d+)(?:x(?P<y>\d+))?(?: (.))?$").unwrap();
+
+        let caps = pat.captures("3x4 q").unwrap();
+        assert_eq!(caps.parse_all::<(u8, u8, char)>(), Ok((3, 4, 'q')));
+        assert_eq!(caps.parse_named::<i64>("y"), Ok(4));
+
+        let caps = pat.captures("300").unwrap();
+        assert_eq!(
+            caps.parse_all::<(usize, usize, char)>(),
+            Err(CaptureError {
+                group: Group::Index(2),
+                source: "300".to_string(),
+                kind: CaptureErrorKind::Missing,
+            }),
+        );
+        assert_eq!(
+            caps.parse_at::<u8>(1).unwrap_err().kind,
+            CaptureErrorKind::BadValue {
+                text: "300".to_string(),
+                reason: "number too large to fit in target type".to_string(),
+            },
+        );
+        assert_eq!(
+            caps.parse_named::<u8>("y").unwrap_err().to_string(),
+            "group `y` of \"300\": no match",
+        );
+    }
+
+    #[test]
+    fn test_parse_named_unknown() {
+        let pat = Regex::new(r"(?P<y>\d+)").unwrap();
+        assert_eq!(
+            pat.captures("3").unwrap().parse_named::<u8>("z"),
+            Err(CaptureError {
+                group: Group::Name("z".to_string()),
+                source: "3".to_string(),
+                kind: CaptureErrorKind::Missing,
+            }),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "regex has 3 capture groups, not 2")]
+    fn test_parse_all_arity() {
+        let pat = Regex::new(r"(a)(b)(c)").unwrap();
+        let _ = pat.captures("abc").unwrap().parse_all::<(char, char)>();
+    }
 }
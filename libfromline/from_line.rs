@@ -0,0 +1,244 @@
+// Copyright © 2016 Bart Massey
+// This program is licensed under the "MIT License".
+// Please see the file COPYING in this distribution
+// for license terms.
+
+//! `#[derive(FromLine)]` for Advent of Code 2016 solutions.
+//!
+//! Deriving `FromLine` for an enum gives it a `FromStr`
+//! impl that tries each variant's `#[pattern("...")]` in
+//! turn, in the order written, and builds the first variant
+//! whose pattern matches. The patterns are
+//! `aoc::LinePattern`s, with two conveniences: in a tuple
+//! variant, `{}` fields are filled in order, and in a
+//! struct variant, fields are named after the variant's
+//! fields. A variant may have several patterns, and unit
+//! variants have patterns with no fields. Errors are
+//! `aoc::LineError`s, naming the failing field as, for
+//! example, `Swap.1`.
+//!
+//! This crate is re-exported by `libaoc`, and the generated
+//! code refers to the `aoc` crate, so use it as
+//! `aoc::FromLine`:
+//!
+//! ```text
+//! #[derive(aoc::FromLine)]
+//! enum Insn {
+//!     #[pattern("swap position {} with position {}")]
+//!     SwPos(usize, usize),
+//!     #[pattern("rotate left {} step")]
+//!     #[pattern("rotate left {} steps")]
+//!     RotL(usize),
+//!     #[pattern("move {count} from {from:char}")]
+//!     Move { from: char, count: usize },
+//! }
+//! ```
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Error, Fields, LitStr, Result, Variant};
+
+/// Derive `FromStr` for an enum from `#[pattern]`
+/// attributes on its variants.
+#[proc_macro_derive(FromLine, attributes(pattern))]
+pub fn derive_from_line(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    from_line(&input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// Generate the `FromStr` impl.
+fn from_line(input: &DeriveInput) -> Result<TokenStream2> {
+    let name = &input.ident;
+    let data = match &input.data {
+        Data::Enum(data) => data,
+        _ => {
+            return Err(Error::new_spanned(
+                name,
+                "FromLine can only be derived for enums",
+            ))
+        }
+    };
+    if !input.generics.params.is_empty() {
+        return Err(Error::new_spanned(
+            &input.generics,
+            "FromLine cannot be derived for generic enums",
+        ));
+    }
+
+    let mut patterns = Vec::new();
+    let mut arms = Vec::new();
+    for variant in &data.variants {
+        let attrs: Vec<LitStr> = variant
+            .attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident("pattern"))
+            .map(|attr| attr.parse_args())
+            .collect::<Result<_>>()?;
+        if attrs.is_empty() {
+            return Err(Error::new_spanned(
+                variant,
+                "FromLine variants need a #[pattern(\"...\")] attribute",
+            ));
+        }
+        for lit in attrs {
+            let (pattern, construct) = variant_pattern(name, variant, &lit)?;
+            let index = patterns.len();
+            patterns.push(pattern);
+            arms.push(quote! {
+                if let Some(record) = patterns[#index].match_line(line) {
+                    return Ok(#construct);
+                }
+            });
+        }
+    }
+
+    let npatterns = patterns.len();
+    Ok(quote! {
+        impl ::std::str::FromStr for #name {
+            type Err = ::aoc::LineError;
+
+            fn from_str(line: &str) -> ::std::result::Result<Self, Self::Err> {
+                static PATTERNS: ::std::sync::OnceLock<[::aoc::LinePattern; #npatterns]> =
+                    ::std::sync::OnceLock::new();
+                let patterns =
+                    PATTERNS.get_or_init(|| [#(::aoc::LinePattern::new(#patterns)),*]);
+                #(#arms)*
+                Err(::aoc::LineError::no_match(line))
+            }
+        }
+    })
+}
+
+/// Piece of a pattern.
+enum Piece {
+    /// Literal text, with braces still escaped.
+    Literal(String),
+    /// A field: its name, possibly empty, and its type
+    /// annotation including the colon, if any.
+    Field(String, String),
+}
+
+/// Split the pattern `lit` into literal text and fields.
+fn scan_pattern(lit: &LitStr) -> Result<Vec<Piece>> {
+    let text = lit.value();
+    let mut pieces = Vec::new();
+    let mut rest = text.as_str();
+    while let Some(i) = rest.find(['{', '}']) {
+        pieces.push(Piece::Literal(rest[..i].to_string()));
+        let tail = &rest[i..];
+        if tail.starts_with("{{") || tail.starts_with("}}") {
+            pieces.push(Piece::Literal(tail[..2].to_string()));
+            rest = &tail[2..];
+            continue;
+        }
+        if tail.starts_with('}') {
+            return Err(Error::new_spanned(lit, "unmatched } in pattern"));
+        }
+        let end = tail
+            .find('}')
+            .ok_or_else(|| Error::new_spanned(lit, "unclosed field in pattern"))?;
+        let spec = &tail[1..end];
+        let (name, ty) = spec.split_at(spec.find(':').unwrap_or(spec.len()));
+        pieces.push(Piece::Field(name.trim().to_string(), ty.to_string()));
+        rest = &tail[end + 1..];
+    }
+    pieces.push(Piece::Literal(rest.to_string()));
+    Ok(pieces)
+}
+
+/// The `LinePattern` text for one pattern of `variant`,
+/// and the expression building the variant from a matched
+/// `record`.
+fn variant_pattern(
+    name: &syn::Ident,
+    variant: &Variant,
+    lit: &LitStr,
+) -> Result<(String, TokenStream2)> {
+    let vname = &variant.ident;
+
+    // Rebuild the pattern with every field named.
+    let mut pattern = String::new();
+    let mut names = Vec::new();
+    for piece in scan_pattern(lit)? {
+        let (field, ty) = match piece {
+            Piece::Literal(text) => {
+                pattern.push_str(&text);
+                continue;
+            }
+            Piece::Field(field, ty) => (field, ty),
+        };
+        let field_name = match (&variant.fields, field.as_str()) {
+            (_, "_") => "_".to_string(),
+            (Fields::Unnamed(_), "") => format!("{}.{}", vname, names.len()),
+            (Fields::Named(named), n)
+                if named.named.iter().any(|f| f.ident.as_ref().unwrap() == n) =>
+            {
+                n.to_string()
+            }
+            _ => {
+                return Err(Error::new_spanned(
+                    lit,
+                    format!(
+                        "field {{{}}} does not fit variant `{}`: use {{}} for tuple \
+                         variants, {{name}} for struct variants, or {{_}} to skip",
+                        field, vname
+                    ),
+                ))
+            }
+        };
+        pattern.push_str(&format!("{{{}{}}}", field_name, ty));
+        if field_name != "_" {
+            names.push(field_name);
+        }
+    }
+
+    let construct = match &variant.fields {
+        Fields::Unit => {
+            if !names.is_empty() {
+                return Err(Error::new_spanned(lit, "unit variants have no fields"));
+            }
+            quote!(#name::#vname)
+        }
+        Fields::Unnamed(unnamed) => {
+            if names.len() != unnamed.unnamed.len() {
+                return Err(Error::new_spanned(
+                    lit,
+                    format!(
+                        "pattern has {} fields but variant `{}` has {}",
+                        names.len(),
+                        vname,
+                        unnamed.unnamed.len()
+                    ),
+                ));
+            }
+            quote!(#name::#vname(#(record.get(#names)?),*))
+        }
+        Fields::Named(named) => {
+            let mut inits = Vec::new();
+            for field in &named.named {
+                let ident = field.ident.as_ref().unwrap();
+                let key = ident.to_string();
+                match names.iter().filter(|&n| *n == key).count() {
+                    1 => inits.push(quote!(#ident: record.get(#key)?)),
+                    0 => {
+                        return Err(Error::new_spanned(
+                            lit,
+                            format!("pattern has no field {{{}}}", key),
+                        ))
+                    }
+                    _ => {
+                        return Err(Error::new_spanned(
+                            lit,
+                            format!("pattern has field {{{}}} more than once", key),
+                        ))
+                    }
+                }
+            }
+            quote!(#name::#vname { #(#inits),* })
+        }
+    };
+    Ok((pattern, construct))
+}